@@ -1,7 +1,12 @@
 #![no_std]
 #![no_main]
 
-use core::{cell::RefCell, iter::Cycle, slice::Iter, sync::atomic::AtomicI32};
+use core::{
+    cell::RefCell,
+    iter::Cycle,
+    slice::Iter,
+    sync::atomic::{AtomicI32, AtomicU32},
+};
 use critical_section::Mutex;
 use embedded_graphics::{
     pixelcolor::Rgb565,
@@ -10,6 +15,10 @@ use embedded_graphics::{
     Drawable,
 };
 use embedded_hal::digital::PinState;
+#[cfg(feature = "edge-interrupt-encoder")]
+use esp_hal::gpio::{Event, Io};
+#[cfg(not(feature = "edge-interrupt-encoder"))]
+use esp_hal::timer::{self, timg::TimerGroup};
 use esp_hal::{
     clock::CpuClock,
     delay::Delay,
@@ -19,20 +28,31 @@ use esp_hal::{
     ram,
     spi::master::Spi,
     time::Duration,
-    timer::{self, timg::TimerGroup, PeriodicTimer},
+    timer::PeriodicTimer,
     Blocking,
 };
+#[cfg(feature = "pcnt-encoder")]
+use esp_hal::{pcnt::Pcnt, peripherals::PCNT};
 use esp_println::println;
+#[cfg(feature = "pcnt-encoder")]
+use focus::hardware::pcnt_encoder::{PcntEncoder, MAX_FILTER_CYCLES};
 use focus::hardware::{
+    clock::SystemClock,
     screen::{self, DisplayDriver},
     spi_bus,
 };
+#[cfg(not(feature = "pcnt-encoder"))]
+use hl_driver::encoder::Hy040;
 use hl_driver::{
     debounce,
-    encoder::{self, Direction, Encode, Hy040},
-    switch::{self, DebouncedSwitch, Pressable},
+    encoder::{AcceleratedEncoder, AccelerationCurve, Position, StepMode},
+    switch::{self, DebouncedSwitch, Pressable, SupervisedSwitch, SupervisorConfig, SwitchError},
 };
 
+// The pulse counter decodes every edge in hardware, it is only read periodically
+#[cfg(all(feature = "pcnt-encoder", feature = "edge-interrupt-encoder"))]
+compile_error!("The `pcnt-encoder` and `edge-interrupt-encoder` features are mutually exclusive");
+
 #[panic_handler]
 fn panic(e: &core::panic::PanicInfo) -> ! {
     println!("Panic: {}", e);
@@ -52,18 +72,44 @@ const MAX_RADIUS: u8 = 120;
 const SCREEN_CENTER: Point = Point::new(MAX_RADIUS as i32, MAX_RADIUS as i32);
 const RADIUS_TO_DIAMETER_FACTOR: u8 = 2;
 const ENCODER_POLLING_TIMER_MS: u8 = 5;
+// Switches debounce durations, independent of the main loop speed
+const SWITCH_PRESS_DEBOUNCE_MS: u32 = 15;
+const SWITCH_RELEASE_DEBOUNCE_MS: u32 = 30;
+
+// Switches are debounced based on the system time, and supervised so a jammed switch cannot lock the UI
+type SystemSwitch<'a> = SupervisedSwitch<
+    DebouncedSwitch<Input<'a>, debounce::TimedDebouncer<SystemClock>>,
+    SystemClock,
+>;
+// Reading the esp-hal inputs cannot fail, the switch errors come from the supervisor
+type SystemSwitchError = SwitchError<core::convert::Infallible>;
 
 static SPI_BUS: Mutex<RefCell<Option<Spi<'static, Blocking>>>> = Mutex::new(RefCell::new(None));
-static HY040: Mutex<RefCell<Option<Hy040<Input<'static>>>>> = Mutex::new(RefCell::new(None));
+// The encoder pins are decoded in software, or in hardware by the pulse counter (`pcnt-encoder` feature)
+#[cfg(not(feature = "pcnt-encoder"))]
+type EncoderBackend<'a> = Hy040<Input<'a>>;
+#[cfg(feature = "pcnt-encoder")]
+type EncoderBackend<'a> = PcntEncoder<'a, 0>;
+// Fast rotations move the radius by several pixels per detent
+type SystemEncoder<'a> = AcceleratedEncoder<EncoderBackend<'a>, SystemClock>;
+
+static HY040: Mutex<RefCell<Option<SystemEncoder<'static>>>> = Mutex::new(RefCell::new(None));
 static HY040_TIMER: Mutex<RefCell<Option<PeriodicTimer<'static, Blocking>>>> =
     Mutex::new(RefCell::new(None));
+// Radius in encoder detents, updated by the encoder ISR
+static RADIUS_POSITION: Mutex<RefCell<Option<Position>>> = Mutex::new(RefCell::new(None));
 static CIRCLE_RADIUS: AtomicI32 = AtomicI32::new(MIN_RADIUS as i32);
+// Encoder transitions missed because the pins are read too slowly for the rotation
+static MISSED_TRANSITIONS: AtomicU32 = AtomicU32::new(0);
+// Encoder readings which failed, reported as rest by the encoder
+static ENCODER_READ_ERRORS: AtomicU32 = AtomicU32::new(0);
 
 #[main]
 fn main() -> ! {
     // generator version: 0.3.1
     let config = esp_hal::Config::default().with_cpu_clock(CpuClock::max());
     let peripherals = esp_hal::init(config);
+    #[cfg(not(feature = "edge-interrupt-encoder"))]
     let timg1 = TimerGroup::new(peripherals.TIMG1);
     let mut delay = Delay::new();
 
@@ -71,11 +117,27 @@ fn main() -> ! {
     let (mut hy040_switch, mut boot_button) = init_switches(peripherals.GPIO6, peripherals.GPIO0);
 
     // Encoder
+    #[cfg(not(feature = "pcnt-encoder"))]
     let hy040 = init_hy040(peripherals.GPIO4, peripherals.GPIO5);
-    // Interrupt Timer for polling the hy040 encoder
-    let mut encoder_timer = timer::PeriodicTimer::new(timg1.timer0);
-    encoder_timer.enable_interrupt(true);
-    encoder_timer.set_interrupt_handler(encoder_isr);
+    #[cfg(feature = "pcnt-encoder")]
+    let hy040 = init_hy040(peripherals.GPIO4, peripherals.GPIO5, peripherals.PCNT);
+    // The encoder pins are read from a periodic timer interrupt, even when the knob is untouched,
+    // or from their any-edge interrupts, only when the knob moves (`edge-interrupt-encoder` feature)
+    #[cfg(not(feature = "edge-interrupt-encoder"))]
+    let encoder_timer = {
+        // Interrupt Timer for polling the hy040 encoder
+        let mut encoder_timer = timer::PeriodicTimer::new(timg1.timer0);
+        encoder_timer.enable_interrupt(true);
+        encoder_timer.set_interrupt_handler(encoder_isr);
+        Some(encoder_timer)
+    };
+    #[cfg(feature = "edge-interrupt-encoder")]
+    let encoder_timer = {
+        // Every edge of CLK or DT is a quadrature transition to decode
+        let mut io = Io::new(peripherals.IO_MUX);
+        io.set_interrupt_handler(encoder_edge_isr);
+        None
+    };
 
     // SPI Bus
     let spi = spi_bus::init_spi_bus(peripherals.SPI2, peripherals.GPIO12, peripherals.GPIO13);
@@ -84,7 +146,10 @@ fn main() -> ! {
     critical_section::with(|cs| {
         SPI_BUS.borrow_ref_mut(cs).replace(spi);
         HY040.borrow_ref_mut(cs).replace(hy040);
-        HY040_TIMER.borrow_ref_mut(cs).replace(encoder_timer);
+        RADIUS_POSITION
+            .borrow_ref_mut(cs)
+            .replace(Position::new(MIN_RADIUS as i32, MAX_RADIUS as i32));
+        *HY040_TIMER.borrow_ref_mut(cs) = encoder_timer;
 
         // Start timer for encoder polling
         let mut timer = HY040_TIMER.borrow_ref_mut(cs);
@@ -112,18 +177,35 @@ fn main() -> ! {
 
     // Shape
     let mut circle = init_background(&mut display_driver, &mut iter);
+    let mut reported_missed_transitions = 0;
+    let mut reported_read_errors = 0;
+    let mut switch_errors = [None; 2];
 
     // Program loop
     loop {
         // check the switched and change color / reset circle
-        switch_handler(&mut boot_button, &mut hy040_switch, &mut circle, &mut iter);
+        switch_handler(
+            &mut boot_button,
+            &mut hy040_switch,
+            &mut switch_errors,
+            &mut circle,
+            &mut iter,
+        );
+
+        // Report the encoder steps lost since the last loop
+        let missed_transitions = MISSED_TRANSITIONS.load(core::sync::atomic::Ordering::Relaxed);
+        if missed_transitions != reported_missed_transitions {
+            println!("Encoder missed transitions: {}", missed_transitions);
+            reported_missed_transitions = missed_transitions;
+        }
+        let read_errors = ENCODER_READ_ERRORS.load(core::sync::atomic::Ordering::Relaxed);
+        if read_errors != reported_read_errors {
+            println!("Encoder read errors: {}", read_errors);
+            reported_read_errors = read_errors;
+        }
 
-        // We retrieve the radius and adjust it so it does not overflow or goes out
-        // of the screen's resolution. This is to avoid panic if values go negative as well as
-        // stopping the encoder of increasing the value when it should not have any effect.
-        let mut radius = CIRCLE_RADIUS.load(core::sync::atomic::Ordering::Relaxed);
-        radius = radius.clamp(MIN_RADIUS as i32, MAX_RADIUS as i32);
-        CIRCLE_RADIUS.store(radius, core::sync::atomic::Ordering::Relaxed);
+        // The radius is already clamped to the screen's resolution by the encoder position.
+        let radius = CIRCLE_RADIUS.load(core::sync::atomic::Ordering::Relaxed);
 
         // Reset the screen as black
         display_driver.fill(BLACK);
@@ -142,19 +224,26 @@ fn main() -> ! {
 
 #[inline]
 fn switch_handler<'a>(
-    boot_button: &mut DebouncedSwitch<Input<'a>, debounce::Debouncer>,
-    hy040_switch: &mut DebouncedSwitch<Input<'a>, debounce::Debouncer>,
+    boot_button: &mut SystemSwitch<'a>,
+    hy040_switch: &mut SystemSwitch<'a>,
+    switch_errors: &mut [Option<SystemSwitchError>; 2],
     circle: &mut Styled<Circle, PrimitiveStyle<Rgb565>>,
     color_iter: &mut Cycle<Iter<'a, Rgb565>>,
 ) {
+    // Faulty switches (read error, stuck or chattering) are ignored until they recover.
     // If boot button is pressed, we reset the radius of the circle.
-    if boot_button.has_been_pressed().unwrap() {
+    if has_been_pressed(boot_button, "Boot", &mut switch_errors[0]) {
         println!("Reset radius");
-        CIRCLE_RADIUS.store(MIN_RADIUS as i32, core::sync::atomic::Ordering::Relaxed);
+        critical_section::with(|cs| {
+            if let Some(position) = RADIUS_POSITION.borrow_ref_mut(cs).as_mut() {
+                position.reset();
+                CIRCLE_RADIUS.store(position.value(), core::sync::atomic::Ordering::Relaxed);
+            }
+        });
     }
 
     // If the encoder button has been pressed, we change the circle's background.
-    if hy040_switch.has_been_pressed().unwrap() {
+    if has_been_pressed(hy040_switch, "Encoder", &mut switch_errors[1]) {
         println!("Changing color");
         if let Some(color) = color_iter.next() {
             circle.style.fill_color = Some(*color);
@@ -162,34 +251,85 @@ fn switch_handler<'a>(
     }
 }
 
+// Read the pressed edge of a switch, its errors are reported once when they appear
+fn has_been_pressed(
+    switch: &mut SystemSwitch<'_>,
+    name: &str,
+    last_error: &mut Option<SystemSwitchError>,
+) -> bool {
+    match switch.has_been_pressed() {
+        Ok(pressed) => {
+            if last_error.take().is_some() {
+                println!("{} switch recovered", name);
+            }
+            pressed
+        }
+        Err(error) => {
+            if last_error.replace(error) != Some(error) {
+                println!("{} switch error: {:?}", name, error);
+            }
+            false
+        }
+    }
+}
+
 fn init_switches<'a>(
     hy040_sw_pin: GPIO6<'a>,
     boot_sw_pin: GPIO0<'a>,
-) -> (
-    DebouncedSwitch<Input<'a>, debounce::Debouncer>,
-    DebouncedSwitch<Input<'a>, debounce::Debouncer>,
-) {
+) -> (SystemSwitch<'a>, SystemSwitch<'a>) {
     // Switches
     let boot_button = switch::Switch::new(
         Input::new(boot_sw_pin, InputConfig::default().with_pull(Pull::Up)),
         PinState::Low,
     )
-    .with_debounce(debounce::Debouncer::default());
+    .with_debounce(debounce::TimedDebouncer::new(
+        SystemClock,
+        SWITCH_PRESS_DEBOUNCE_MS,
+        SWITCH_RELEASE_DEBOUNCE_MS,
+    ));
+    let boot_button = SupervisedSwitch::new(boot_button, SystemClock, SupervisorConfig::default());
 
     let hy040_switch = switch::Switch::new(
         Input::new(hy040_sw_pin, InputConfig::default().with_pull(Pull::Up)),
         PinState::Low,
     )
-    .with_debounce(debounce::Debouncer::default());
+    .with_debounce(debounce::TimedDebouncer::new(
+        SystemClock,
+        SWITCH_PRESS_DEBOUNCE_MS,
+        SWITCH_RELEASE_DEBOUNCE_MS,
+    ));
+    let hy040_switch =
+        SupervisedSwitch::new(hy040_switch, SystemClock, SupervisorConfig::default());
 
     (hy040_switch, boot_button)
 }
 
-fn init_hy040<'a>(clk: GPIO4<'a>, dt: GPIO5<'a>) -> Hy040<Input<'a>> {
+#[cfg(not(feature = "pcnt-encoder"))]
+fn init_hy040<'a>(clk: GPIO4<'a>, dt: GPIO5<'a>) -> SystemEncoder<'a> {
     let config = InputConfig::default().with_pull(Pull::Up);
     let clk = Input::new(clk, config);
     let dt = Input::new(dt, config);
-    encoder::Hy040::new(clk, dt)
+    #[cfg(feature = "edge-interrupt-encoder")]
+    let (clk, dt) = {
+        let (mut clk, mut dt) = (clk, dt);
+        clk.listen(Event::AnyEdge);
+        dt.listen(Event::AnyEdge);
+        (clk, dt)
+    };
+    // One step per mechanical detent
+    let hy040 = Hy040::new(clk, dt).with_step_mode(StepMode::Full);
+    AcceleratedEncoder::new(hy040, SystemClock, AccelerationCurve::default())
+}
+
+#[cfg(feature = "pcnt-encoder")]
+fn init_hy040<'a>(clk: GPIO4<'a>, dt: GPIO5<'a>, pcnt: PCNT<'a>) -> SystemEncoder<'a> {
+    let config = InputConfig::default().with_pull(Pull::Up);
+    let clk = Input::new(clk, config);
+    let dt = Input::new(dt, config);
+    // One step per mechanical detent, the pulse counter filters the shortest glitches
+    let pcnt = Pcnt::new(pcnt);
+    let hy040 = PcntEncoder::new(pcnt.unit0, clk, dt, StepMode::Full, MAX_FILTER_CYCLES);
+    AcceleratedEncoder::new(hy040, SystemClock, AccelerationCurve::default())
 }
 
 fn init_background<'a>(
@@ -209,6 +349,7 @@ fn init_background<'a>(
     circle
 }
 
+#[cfg(not(feature = "edge-interrupt-encoder"))]
 #[handler]
 #[ram]
 fn encoder_isr() {
@@ -216,24 +357,60 @@ fn encoder_isr() {
         // Retreive objects from mutexes
         let mut hy040 = HY040.borrow_ref_mut(cs);
         let mut timer = HY040_TIMER.borrow_ref_mut(cs);
+        let mut position = RADIUS_POSITION.borrow_ref_mut(cs);
 
         // If we have retrieved them,
-        if let (Some(hy040), Some(timer)) = (hy040.as_mut(), timer.as_mut()) {
-            // Check the direction in which the encoder is rotated
-            match hy040.encode() {
-                Direction::Clockwise => {
-                    // Increase the radius
-                    CIRCLE_RADIUS.fetch_add(1, core::sync::atomic::Ordering::Relaxed)
-                }
-                Direction::CounterClockwise => {
-                    // Deacrease the radius
-                    CIRCLE_RADIUS.fetch_sub(1, core::sync::atomic::Ordering::Relaxed)
-                }
-                // Do nothing
-                Direction::Rest => 0,
-            };
+        if let (Some(hy040), Some(timer), Some(position)) =
+            (hy040.as_mut(), timer.as_mut(), position.as_mut())
+        {
+            // Increase the radius clockwise and decrease it counter clockwise, within the screen
+            let radius = position.step(hy040.poll());
+            CIRCLE_RADIUS.store(radius, core::sync::atomic::Ordering::Relaxed);
+            // The pulse counter neither misses transitions nor fails to read
+            #[cfg(not(feature = "pcnt-encoder"))]
+            {
+                MISSED_TRANSITIONS.store(
+                    hy040.encoder().missed_transitions(),
+                    core::sync::atomic::Ordering::Relaxed,
+                );
+                ENCODER_READ_ERRORS.store(
+                    hy040.encoder().read_errors(),
+                    core::sync::atomic::Ordering::Relaxed,
+                );
+            }
             // Clear the timer interrupt to allow for a new cycle, otherwise it triggers infinitely.
             timer.clear_interrupt();
         }
     });
 }
+
+#[cfg(feature = "edge-interrupt-encoder")]
+#[handler]
+#[ram]
+fn encoder_edge_isr() {
+    critical_section::with(|cs| {
+        // Retreive objects from mutexes
+        let mut hy040 = HY040.borrow_ref_mut(cs);
+        let mut position = RADIUS_POSITION.borrow_ref_mut(cs);
+
+        // If we have retrieved them,
+        if let (Some(hy040), Some(position)) = (hy040.as_mut(), position.as_mut()) {
+            // Clear the pins interrupts first, so an edge happening while decoding triggers a new cycle
+            let (clk, dt) = hy040.encoder_mut().pins_mut();
+            clk.clear_interrupt();
+            dt.clear_interrupt();
+
+            // Increase the radius clockwise and decrease it counter clockwise, within the screen
+            let radius = position.step(hy040.poll());
+            CIRCLE_RADIUS.store(radius, core::sync::atomic::Ordering::Relaxed);
+            MISSED_TRANSITIONS.store(
+                hy040.encoder().missed_transitions(),
+                core::sync::atomic::Ordering::Relaxed,
+            );
+            ENCODER_READ_ERRORS.store(
+                hy040.encoder().read_errors(),
+                core::sync::atomic::Ordering::Relaxed,
+            );
+        }
+    });
+}
@@ -1,3 +1,5 @@
 pub mod button;
+pub mod clock;
+pub mod pcnt_encoder;
 pub mod screen;
 pub mod spi_bus;
@@ -0,0 +1,13 @@
+use esp_hal::time::Instant;
+use hl_driver::time::Monotonic;
+
+/// Monotonic clock based on the esp-hal system timer, counting from boot.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Monotonic for SystemClock {
+    #[inline]
+    fn now_ms(&self) -> u64 {
+        Instant::now().duration_since_epoch().as_millis()
+    }
+}
@@ -0,0 +1,150 @@
+use core::convert::Infallible;
+
+use esp_hal::{
+    gpio::Input,
+    pcnt::{
+        channel::{CtrlMode, EdgeMode},
+        unit::Unit,
+    },
+};
+use hl_driver::encoder::{Direction, Encode, EncoderError, StepMode};
+
+// The hardware counter goes back to 0 on reaching +/- this limit
+const COUNTER_LIMIT: i16 = 30_000;
+// Largest glitch filter of the pulse counter, in APB clock cycles (~12.8 us at 80 MHz)
+pub const MAX_FILTER_CYCLES: u16 = 1023;
+
+/// ## Description
+/// Quadrature encoder decoded in hardware by a pulse counter (PCNT) unit.
+///
+/// Both edges of both pins are counted, so the counter moves by 4 per detent of a HY040, positive
+/// clockwise like `hl_driver::encoder::Hy040`. Pulses shorter than the filter are ignored by the
+/// peripheral, and no transition is lost between two readings, however slow the polling.
+///
+/// The steps are still reported one per reading through `Encode`: steps piled up between two
+/// readings are reported on the following readings, which an `AcceleratedEncoder` sees as a fast
+/// rotation. Poll the encoder faster than the detents (every few milliseconds) to keep the
+/// acceleration accurate.
+pub struct PcntEncoder<'d, const NUM: usize> {
+    unit: Unit<'d, NUM>,
+    step: i32,
+    last_value: i16,
+    count: i32,
+    pending: i32,
+}
+
+impl<'d, const NUM: usize> PcntEncoder<'d, NUM> {
+    /// ## Description
+    /// Configure a pulse counter unit to decode the clk (A) and dt (B) pins of an encoder.
+    /// ## Parameters
+    /// - `unit`: the pulse counter unit decoding the pins
+    /// - `clk`: the gpio pin connected to the A pin of the encoder
+    /// - `dt`: the gpio pin connected to the B pin of the encoder
+    /// - `step_mode`: number of transitions per reported step
+    /// - `filter_cycles`: shortest pulse taken into account, in APB clock cycles
+    ///   (capped to `MAX_FILTER_CYCLES`)
+    /// ## Return
+    /// - PcntEncoder
+    pub fn new(
+        unit: Unit<'d, NUM>,
+        clk: Input<'d>,
+        dt: Input<'d>,
+        step_mode: StepMode,
+        filter_cycles: u16,
+    ) -> Self {
+        unit.set_low_limit(Some(-COUNTER_LIMIT)).unwrap();
+        unit.set_high_limit(Some(COUNTER_LIMIT)).unwrap();
+        unit.set_filter(Some(filter_cycles.min(MAX_FILTER_CYCLES)))
+            .unwrap();
+        unit.clear();
+
+        let (clk, _) = clk.split();
+        let (dt, _) = dt.split();
+        // Channel 0 counts the edges of dt, in the direction given by the level of clk
+        let channel = &unit.channel0;
+        channel.set_ctrl_signal(clk.clone());
+        channel.set_edge_signal(dt.clone());
+        channel.set_ctrl_mode(CtrlMode::Reverse, CtrlMode::Keep);
+        channel.set_input_mode(EdgeMode::Increment, EdgeMode::Decrement);
+        // Channel 1 counts the edges of clk, in the direction given by the level of dt
+        let channel = &unit.channel1;
+        channel.set_ctrl_signal(dt);
+        channel.set_edge_signal(clk);
+        channel.set_ctrl_mode(CtrlMode::Reverse, CtrlMode::Keep);
+        channel.set_input_mode(EdgeMode::Decrement, EdgeMode::Increment);
+        unit.resume();
+
+        PcntEncoder {
+            unit,
+            step: step_mode.transitions() as i32,
+            last_value: 0,
+            count: 0,
+            pending: 0,
+        }
+    }
+
+    /// ## Description
+    /// Transitions counted since the creation or the last reset.
+    /// ## Return
+    /// - `i32`: number of transitions, positive clockwise
+    pub fn count(&self) -> i32 {
+        self.count
+    }
+
+    /// ## Description
+    /// Position of the encoder in steps of its `StepMode`.
+    /// ## Return
+    /// - `i32`: number of steps, positive clockwise
+    pub fn position(&self) -> i32 {
+        self.count.div_euclid(self.step)
+    }
+
+    /// ## Description
+    /// Move the position back to 0, the steps not reported yet are dropped.
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.pending = 0;
+    }
+
+    /// ## Description
+    /// Accumulate the transitions counted since the last reading.
+    /// The counter has to be read before it moves by half of its limit (15000 transitions).
+    fn update(&mut self) {
+        let value = self.unit.value();
+        // The counter goes back to 0 at both limits, so it is only known modulo the limit
+        let limit = COUNTER_LIMIT as i32;
+        let mut delta = (value as i32 - self.last_value as i32).rem_euclid(limit);
+        if delta > limit / 2 {
+            delta -= limit;
+        }
+        self.last_value = value;
+        self.count = self.count.wrapping_add(delta);
+        self.pending = self.pending.saturating_add(delta);
+    }
+}
+
+impl<const NUM: usize> Encode for PcntEncoder<'_, NUM> {
+    // Reading the counter cannot fail
+    type PinError = Infallible;
+
+    /// ## Description
+    /// Report one step per call while the counter is ahead of the reported steps.
+    /// The steps counted between two calls are not reported at once, they are spread over the
+    /// following calls.
+    /// ## Return
+    /// *Result<Direction, EncoderError>*
+    /// - `Direction`: Clockwise or CounterClockwise while steps are pending, Rest otherwise
+    /// - `EncoderError`: never, reading the counter cannot fail
+    fn try_encode(&mut self) -> Result<Direction, EncoderError<Self::PinError>> {
+        self.update();
+        Ok(if self.pending >= self.step {
+            self.pending -= self.step;
+            Direction::Clockwise
+        } else if self.pending <= -self.step {
+            self.pending += self.step;
+            Direction::CounterClockwise
+        } else {
+            Direction::Rest
+        })
+    }
+}
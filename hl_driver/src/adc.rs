@@ -0,0 +1,33 @@
+use core::fmt::Debug;
+
+/// ## Description
+///
+/// Trait defining a single analog input (embedded-hal 1.0 has no ADC trait).
+/// Implementations are expected to convert the raw reading of the ADC to millivolts,
+/// applying the calibration of the chip if any.
+pub trait AnalogInput {
+    type Error: Debug;
+
+    /// ## Description
+    /// Sample the analog input once.
+    /// ## Return
+    /// *Result<u16, Self::Error>*
+    /// - `u16`: voltage of the input in millivolts
+    /// - `Self::Error`: the conversion failed
+    fn read_mv(&mut self) -> Result<u16, Self::Error>;
+}
+
+/// ## Description
+///
+/// Allow an analog input to be borrowed by a driver.
+impl<A> AnalogInput for &mut A
+where
+    A: AnalogInput + ?Sized,
+{
+    type Error = A::Error;
+
+    #[inline]
+    fn read_mv(&mut self) -> Result<u16, Self::Error> {
+        (**self).read_mv()
+    }
+}
@@ -1,5 +1,20 @@
+use core::cell::Cell;
+use core::fmt::Debug;
+use core::ops::{BitAnd, BitOr, BitXor, Not};
+
+use crate::time::Monotonic;
+
+// Historical debounce window: 3 active ticks to press, a full u8 register of inactive ticks to release.
+const DEFAULT_PRESS_SAMPLES: u8 = 3;
+const DEFAULT_RELEASE_SAMPLES: u8 = 8;
+// Register contents of the historical window, see `From<u8> for DebounceState`
 const DEBOUNCE_MASK: u8 = 0x07;
 const RELEASED_MASK: u8 = 0x00;
+// Integrator thresholds: 3 net active samples to press, back to 0 to release.
+const DEFAULT_LOWER_THRESHOLD: u8 = 0;
+const DEFAULT_UPPER_THRESHOLD: u8 = 3;
+// Capacity of the 2 bits vertical counters of a bank
+const BANK_MAX_SAMPLES: u8 = 4;
 
 /// ## Description
 ///
@@ -12,7 +27,7 @@ pub trait Debounce {
 /// ## Description
 ///
 /// Possible state for a debouncer
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum DebounceState {
     Loaded,
     Unloaded,
@@ -22,7 +37,8 @@ pub enum DebounceState {
 /// ## Description
 ///
 /// Allow rapid conversion between u8 and the Debounce state.
-/// Useful for converting the state of a register.
+/// Useful for converting the state of a register filled with the historical window of the default
+/// `Debouncer` (3 active samples to press, 8 inactive samples to release).
 impl From<u8> for DebounceState {
     fn from(value: u8) -> Self {
         match value {
@@ -33,11 +49,141 @@ impl From<u8> for DebounceState {
     }
 }
 
+/// ## Description
+///
+/// Possible misconfigurations of a debouncer
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DebounceError {
+    PressSamples,   // The press sample count is 0 or does not fit in the window
+    ReleaseSamples, // The release sample count is 0 or does not fit in the window
+    Thresholds,     // The lower threshold is not strictly lower than the upper threshold
+}
+
+/// ## Description
+///
+/// Check that press and release sample counts fit in a window of `N` samples.
+fn check_samples<const N: usize>(
+    press_samples: u8,
+    release_samples: u8,
+) -> Result<(), DebounceError> {
+    if press_samples == 0 || press_samples as usize > N {
+        return Err(DebounceError::PressSamples);
+    }
+    if release_samples == 0 || release_samples as usize > N {
+        return Err(DebounceError::ReleaseSamples);
+    }
+    Ok(())
+}
+
+/// ## Description
+///
+/// Unsigned integer used as a sliding window of samples by the `Debouncer`,
+/// or as a set of parallel inputs by the `DebounceBank`.
+/// The most recent sample is always the least significant bit.
+pub trait Register:
+    Copy
+    + Debug
+    + Ord
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+{
+    /// Register without any active sample.
+    const EMPTY: Self;
+    /// Number of bits of the register.
+    const BITS: u32;
+
+    /// ## Description
+    /// Shift the register by one sample and insert the new one as the least significant bit.
+    fn shift_in(self, sample: bool) -> Self;
+
+    /// ## Description
+    /// Build a register with the `len` least significant bits set.
+    /// Saturates to a full register when `len` is larger than the register width.
+    fn mask(len: u8) -> Self;
+
+    /// ## Description
+    /// Build a register with only the bit at `index` set.
+    /// ## Panics
+    /// If `index` is not lower than `BITS`.
+    fn bit(index: u8) -> Self;
+}
+
+macro_rules! impl_register {
+    ($($reg:ty),*) => {
+        $(
+            impl Register for $reg {
+                const EMPTY: Self = 0;
+                const BITS: u32 = <$reg>::BITS;
+
+                #[inline]
+                fn shift_in(self, sample: bool) -> Self {
+                    (self << 1) | sample as $reg
+                }
+
+                #[inline]
+                fn mask(len: u8) -> Self {
+                    if len as u32 >= Self::BITS {
+                        <$reg>::MAX
+                    } else {
+                        (1 << len) - 1
+                    }
+                }
+
+                #[inline]
+                fn bit(index: u8) -> Self {
+                    assert!((index as u32) < Self::BITS, "The register has no such bit");
+                    1 << index
+                }
+            }
+        )*
+    };
+}
+
+impl_register!(u8, u16, u32, u64);
+
+/// ## Description
+///
+/// Size, in samples, of a debounce window.
+/// Only the sizes for which a backing register exists (8, 16, 32 and 64) implement `WindowRegister`.
+#[derive(Debug)]
+pub struct Window<const N: usize>;
+
+/// ## Description
+///
+/// Select the unsigned integer backing a debounce window of a given size.
+pub trait WindowRegister {
+    type Register: Register;
+}
+
+impl WindowRegister for Window<8> {
+    type Register = u8;
+}
+
+impl WindowRegister for Window<16> {
+    type Register = u16;
+}
+
+impl WindowRegister for Window<32> {
+    type Register = u32;
+}
+
+impl WindowRegister for Window<64> {
+    type Register = u64;
+}
+
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 /// ## Description
 ///
-/// Debouncer struct implementing the debouncing trait based on a u8 register
+/// Debouncer struct implementing the debouncing trait based on a `N` bits register
+/// (backed by a u8, u16, u32 or u64).
+///
+/// - The debouncer is `Loaded` when the last `press_samples` samples are active and were preceded by
+///   `release_samples` inactive samples (or as many as the register can hold).
+/// - The debouncer is `Unloaded` when the last `release_samples` samples are inactive.
+/// - Any other register content is a `Transition`.
 ///
 /// ## Example
 ///
@@ -50,25 +196,665 @@ impl From<u8> for DebounceState {
 ///     // Retrieve the DebounceState. When the register is full (3 ticks), the state will be Loaded.
 ///     let state = debouncer.get_state();
 ///     assert_eq!(DebounceState::Transition, state);
+///
+///     // A slower switch on a 16 samples window, pressed after 5 ticks and released after 12 ticks.
+///     let mut debouncer = Debouncer::<16>::new(5, 12);
+///     for _ in 0..5 {
+///         debouncer.debounce(true);
+///     }
+///     assert_eq!(DebounceState::Loaded, debouncer.get_state());
+/// ```
+pub struct Debouncer<const N: usize = 8>
+where
+    Window<N>: WindowRegister,
+{
+    register: <Window<N> as WindowRegister>::Register,
+    press_samples: u8,
+    release_samples: u8,
+}
+
+impl<const N: usize> Debouncer<N>
+where
+    Window<N>: WindowRegister,
+{
+    /// ## Description
+    ///
+    /// Create a debouncer with an empty register and custom press and release sample counts.
+    ///
+    /// ## Parameters
+    /// - `press_samples`: number of consecutive active samples needed to consider the debouncer `Loaded`
+    /// - `release_samples`: number of consecutive inactive samples needed to consider the debouncer `Unloaded`
+    ///
+    /// ## Panics
+    /// If one of the sample counts is 0 or larger than the window size `N`.
+    ///
+    /// ## Return
+    /// - Debouncer
+    pub fn new(press_samples: u8, release_samples: u8) -> Self {
+        match Self::try_new(press_samples, release_samples) {
+            Ok(debouncer) => debouncer,
+            Err(DebounceError::PressSamples) => {
+                panic!("Press samples must fit in the debounce window")
+            }
+            Err(_) => panic!("Release samples must fit in the debounce window"),
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Fallible version of `new`, for sample counts only known at runtime.
+    ///
+    /// ## Return
+    /// *Result<Debouncer, DebounceError>*
+    /// - `DebounceError::PressSamples`: `press_samples` is 0 or larger than the window size `N`
+    /// - `DebounceError::ReleaseSamples`: `release_samples` is 0 or larger than the window size `N`
+    pub fn try_new(press_samples: u8, release_samples: u8) -> Result<Self, DebounceError> {
+        check_samples::<N>(press_samples, release_samples)?;
+        Ok(Debouncer {
+            register: <Window<N> as WindowRegister>::Register::EMPTY,
+            press_samples,
+            release_samples,
+        })
+    }
+}
+
+/// ## Description
+///
+/// Default debouncer on a u8 register: 3 ticks to press, 8 ticks to release.
+impl Default for Debouncer {
+    fn default() -> Self {
+        Debouncer::new(DEFAULT_PRESS_SAMPLES, DEFAULT_RELEASE_SAMPLES)
+    }
+}
+
+impl<const N: usize> Debounce for Debouncer<N>
+where
+    Window<N>: WindowRegister,
+{
+    #[inline]
+    fn debounce(&mut self, state: bool) {
+        self.register = self.register.shift_in(state);
+    }
+
+    #[inline]
+    fn get_state(&self) -> DebounceState {
+        type Reg<const N: usize> = <Window<N> as WindowRegister>::Register;
+
+        let press_window = self.press_samples.saturating_add(self.release_samples);
+        if self.register & Reg::<N>::mask(press_window) == Reg::<N>::mask(self.press_samples) {
+            DebounceState::Loaded
+        } else if self.register & Reg::<N>::mask(self.release_samples) == Reg::<N>::EMPTY {
+            DebounceState::Unloaded
+        } else {
+            DebounceState::Transition
+        }
+    }
+}
+
+/// ## Description
+///
+/// Debouncer latching the last confirmed level, based on a `N` bits register (backed by a u8, u16, u32 or u64).
+///
+/// Unlike the `Debouncer`, which only reports `Loaded` on the tick the press is confirmed, the latching
+/// debouncer stays `Loaded` while the input is held and `Unloaded` while it is released.
+/// The state only changes once the opposite level has been confirmed:
+/// - `Loaded` after `press_samples` consecutive active samples
+/// - `Unloaded` after `release_samples` consecutive inactive samples
+///
+/// The `Transition` state is never reported, bounces keep the previously latched level.
+///
+/// ## Example
+///
+/// ```rust
+///     use hl_driver::debounce::{LatchingDebouncer, DebounceState, Debounce};
+///     let mut debouncer = LatchingDebouncer::default();
+///     for _ in 0..10 {
+///         debouncer.debounce(true);
+///     }
+///     // Still loaded while the input is held
+///     assert_eq!(DebounceState::Loaded, debouncer.get_state());
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct LatchingDebouncer<const N: usize = 8>
+where
+    Window<N>: WindowRegister,
+{
+    register: <Window<N> as WindowRegister>::Register,
+    press_samples: u8,
+    release_samples: u8,
+    latched: DebounceState,
+}
+
+impl<const N: usize> LatchingDebouncer<N>
+where
+    Window<N>: WindowRegister,
+{
+    /// ## Description
+    ///
+    /// Create a latching debouncer in the `Unloaded` state with custom press and release sample counts.
+    ///
+    /// ## Parameters
+    /// - `press_samples`: number of consecutive active samples needed to latch the `Loaded` state
+    /// - `release_samples`: number of consecutive inactive samples needed to latch the `Unloaded` state
+    ///
+    /// ## Panics
+    /// If one of the sample counts is 0 or larger than the window size `N`.
+    ///
+    /// ## Return
+    /// - LatchingDebouncer
+    pub fn new(press_samples: u8, release_samples: u8) -> Self {
+        match Self::try_new(press_samples, release_samples) {
+            Ok(debouncer) => debouncer,
+            Err(DebounceError::PressSamples) => {
+                panic!("Press samples must fit in the debounce window")
+            }
+            Err(_) => panic!("Release samples must fit in the debounce window"),
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Fallible version of `new`, for sample counts only known at runtime.
+    ///
+    /// ## Return
+    /// *Result<LatchingDebouncer, DebounceError>*
+    /// - `DebounceError::PressSamples`: `press_samples` is 0 or larger than the window size `N`
+    /// - `DebounceError::ReleaseSamples`: `release_samples` is 0 or larger than the window size `N`
+    pub fn try_new(press_samples: u8, release_samples: u8) -> Result<Self, DebounceError> {
+        check_samples::<N>(press_samples, release_samples)?;
+        Ok(LatchingDebouncer {
+            register: <Window<N> as WindowRegister>::Register::EMPTY,
+            press_samples,
+            release_samples,
+            latched: DebounceState::Unloaded,
+        })
+    }
+}
+
+/// ## Description
+///
+/// Default latching debouncer on a u8 register: 3 ticks to press, 8 ticks to release.
+impl Default for LatchingDebouncer {
+    fn default() -> Self {
+        LatchingDebouncer::new(DEFAULT_PRESS_SAMPLES, DEFAULT_RELEASE_SAMPLES)
+    }
+}
+
+impl<const N: usize> Debounce for LatchingDebouncer<N>
+where
+    Window<N>: WindowRegister,
+{
+    #[inline]
+    fn debounce(&mut self, state: bool) {
+        type Reg<const N: usize> = <Window<N> as WindowRegister>::Register;
+
+        self.register = self.register.shift_in(state);
+
+        let press_mask = Reg::<N>::mask(self.press_samples);
+        if self.register & press_mask == press_mask {
+            self.latched = DebounceState::Loaded;
+        } else if self.register & Reg::<N>::mask(self.release_samples) == Reg::<N>::EMPTY {
+            self.latched = DebounceState::Unloaded;
+        }
+    }
+
+    #[inline]
+    fn get_state(&self) -> DebounceState {
+        self.latched
+    }
+}
+
+/// ## Description
+///
+/// Integrator (counter based) debouncer with hysteresis.
+///
+/// An internal counter is incremented on every active sample and decremented on every inactive one,
+/// saturating between 0 and the `upper` threshold.
+/// - `Loaded` is latched when the counter reaches the `upper` threshold
+/// - `Unloaded` is latched when the counter falls back to the `lower` threshold
+///
+/// In between, the previously latched level is kept, so an isolated glitch during a long press only
+/// moves the counter by one step instead of resetting the state.
+///
+/// ## Example
+///
+/// ```rust
+///     use hl_driver::debounce::{IntegratorDebouncer, DebounceState, Debounce};
+///     let mut debouncer = IntegratorDebouncer::new(1, 4);
+///     for _ in 0..4 {
+///         debouncer.debounce(true);
+///     }
+///     assert_eq!(DebounceState::Loaded, debouncer.get_state());
+///     // A single glitch does not release the switch
+///     debouncer.debounce(false);
+///     assert_eq!(DebounceState::Loaded, debouncer.get_state());
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IntegratorDebouncer {
+    counter: u8,
+    lower: u8,
+    upper: u8,
+    loaded: bool,
+}
+
+impl IntegratorDebouncer {
+    /// ## Description
+    ///
+    /// Create an integrator debouncer in the `Unloaded` state with an empty counter.
+    ///
+    /// ## Parameters
+    /// - `lower`: counter value at (or below) which the debouncer becomes `Unloaded`
+    /// - `upper`: counter value at which the debouncer becomes `Loaded`, also the counter saturation value
+    ///
+    /// ## Panics
+    /// If `lower` is not strictly lower than `upper`.
+    ///
+    /// ## Return
+    /// - IntegratorDebouncer
+    pub fn new(lower: u8, upper: u8) -> Self {
+        Self::try_new(lower, upper)
+            .expect("Lower threshold must be strictly lower than the upper threshold")
+    }
+
+    /// ## Description
+    ///
+    /// Fallible version of `new`, for thresholds only known at runtime.
+    ///
+    /// ## Return
+    /// *Result<IntegratorDebouncer, DebounceError>*
+    /// - `DebounceError::Thresholds`: `lower` is not strictly lower than `upper`
+    pub fn try_new(lower: u8, upper: u8) -> Result<Self, DebounceError> {
+        if lower >= upper {
+            return Err(DebounceError::Thresholds);
+        }
+        Ok(IntegratorDebouncer {
+            counter: 0,
+            lower,
+            upper,
+            loaded: false,
+        })
+    }
+}
+
+/// ## Description
+///
+/// Default integrator debouncer: 3 net active samples to press, back to 0 to release.
+impl Default for IntegratorDebouncer {
+    fn default() -> Self {
+        IntegratorDebouncer::new(DEFAULT_LOWER_THRESHOLD, DEFAULT_UPPER_THRESHOLD)
+    }
+}
+
+impl Debounce for IntegratorDebouncer {
+    #[inline]
+    fn debounce(&mut self, state: bool) {
+        if state {
+            self.counter = self.counter.saturating_add(1).min(self.upper);
+        } else {
+            self.counter = self.counter.saturating_sub(1);
+        }
+
+        if self.counter >= self.upper {
+            self.loaded = true;
+        } else if self.counter <= self.lower {
+            self.loaded = false;
+        }
+    }
+
+    #[inline]
+    fn get_state(&self) -> DebounceState {
+        if self.loaded {
+            DebounceState::Loaded
+        } else {
+            DebounceState::Unloaded
+        }
+    }
+}
+
+/// ## Description
+///
+/// Debouncer based on elapsed time instead of a number of samples.
+/// The debounce duration is therefore independent of the rate at which the input is sampled.
+///
+/// - `Loaded` once the input has been active for `press_ms` milliseconds
+/// - `Unloaded` once the input has been inactive for `release_ms` milliseconds
+///
+/// Like the `LatchingDebouncer`, the `Transition` state is never reported: the confirmed level is kept
+/// while the input bounces, so a bounce during a long press is not seen as a new press.
+///
+/// ## Example
+///
+/// ```rust
+///     use core::cell::Cell;
+///     use hl_driver::debounce::{TimedDebouncer, DebounceState, Debounce};
+///     use hl_driver::time::Monotonic;
+///
+///     // Clock driven by hand for the sake of the example
+///     struct Clock(Cell<u64>);
+///     impl Monotonic for Clock {
+///         fn now_ms(&self) -> u64 {
+///             self.0.get()
+///         }
+///     }
+///
+///     let clock = Clock(Cell::new(0));
+///     let mut debouncer = TimedDebouncer::new(&clock, 20, 50);
+///     debouncer.debounce(true);
+///     assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+///     // 20 ms later, the input is still active
+///     clock.0.set(20);
+///     debouncer.debounce(true);
+///     assert_eq!(DebounceState::Loaded, debouncer.get_state());
 /// ```
-#[derive(Default)]
-pub struct Debouncer {
-    register: u8,
+#[derive(Debug)]
+pub struct TimedDebouncer<C>
+where
+    C: Monotonic,
+{
+    clock: C,
+    press_ms: u32,
+    release_ms: u32,
+    level: bool,
+    sample: bool,
+    since: u64,
+}
+
+impl<C> TimedDebouncer<C>
+where
+    C: Monotonic,
+{
+    /// ## Description
+    ///
+    /// Create a time based debouncer in the `Unloaded` state.
+    ///
+    /// ## Parameters
+    /// - `clock`: a clock implementing `hl_driver::time::Monotonic`
+    /// - `press_ms`: duration the input has to stay active to consider the debouncer `Loaded`
+    /// - `release_ms`: duration the input has to stay inactive to consider the debouncer `Unloaded`
+    ///
+    /// ## Return
+    /// - TimedDebouncer
+    pub fn new(clock: C, press_ms: u32, release_ms: u32) -> Self {
+        let since = clock.now_ms();
+        TimedDebouncer {
+            clock,
+            press_ms,
+            release_ms,
+            level: false,
+            sample: false,
+            since,
+        }
+    }
 }
 
-impl Debounce for Debouncer {
+impl<C> Debounce for TimedDebouncer<C>
+where
+    C: Monotonic,
+{
+    #[inline]
     fn debounce(&mut self, state: bool) {
-        self.register = (self.register << 1) | state as u8;
+        let now = self.clock.now_ms();
+
+        // Restart the stability timer on every change of the input
+        if state != self.sample {
+            self.sample = state;
+            self.since = now;
+        }
+
+        if self.sample != self.level {
+            let required_ms = if self.sample {
+                self.press_ms
+            } else {
+                self.release_ms
+            };
+            if now.saturating_sub(self.since) >= required_ms as u64 {
+                self.level = self.sample;
+            }
+        }
     }
 
+    #[inline]
     fn get_state(&self) -> DebounceState {
-        DebounceState::from(self.register)
+        if self.level {
+            DebounceState::Loaded
+        } else {
+            DebounceState::Unloaded
+        }
+    }
+}
+
+/// ## Description
+///
+/// Bank of debouncers handling up to 8, 16, 32 or 64 inputs in parallel (depending on the register `R`)
+/// using 2 bits vertical counters.
+///
+/// Each bit of the register is an independent input, active when set.
+/// An input has to be sampled 4 consecutive times (1 to 4 with `with_samples`) at the opposite of its
+/// debounced level for the change to be acknowledged. All the inputs are processed with a handful of
+/// bitwise operations per update, which makes the bank cheap enough to be updated from an ISR.
+///
+/// The bank is updated through a shared reference, so the inputs seen as switches
+/// (`hl_driver::switch::BankSwitch`) can be kept across the updates.
+///
+/// ## Example
+///
+/// ```rust
+///     use hl_driver::debounce::{DebounceBank, DebounceState};
+///     use hl_driver::switch::{BankSwitch, Pressable};
+///
+///     let bank = DebounceBank::<u8>::new();
+///     // Individual inputs can be used as switches
+///     let mut button = BankSwitch::new(&bank, 0);
+///
+///     // Input 0 and 3 are active for 4 samples
+///     for _ in 0..3 {
+///         bank.update(0b0000_1001);
+///     }
+///     let edges = bank.update(0b0000_1001);
+///     assert_eq!(0b0000_1001, edges.pressed);
+///     assert_eq!(DebounceState::Loaded, bank.get_state(3));
+///     assert!(button.has_been_pressed().unwrap());
+/// ```
+#[derive(Debug)]
+pub struct DebounceBank<R>
+where
+    R: Register,
+{
+    state: Cell<R>,
+    count_low: Cell<R>,
+    count_high: Cell<R>,
+    // Counter value of the inputs at their debounced level, as two bit planes
+    reset_low: R,
+    reset_high: R,
+    pressed: Cell<R>,
+    released: Cell<R>,
+}
+
+/// ## Description
+///
+/// Inputs whose debounced level changed during a `DebounceBank` update.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BankEdges<R>
+where
+    R: Register,
+{
+    pub pressed: R,
+    pub released: R,
+}
+
+impl<R> DebounceBank<R>
+where
+    R: Register,
+{
+    /// ## Description
+    ///
+    /// Create a bank with all its inputs released, acknowledging a change after 4 samples.
+    ///
+    /// ## Return
+    /// - DebounceBank
+    pub fn new() -> Self {
+        DebounceBank {
+            state: Cell::new(R::EMPTY),
+            count_low: Cell::new(!R::EMPTY),
+            count_high: Cell::new(!R::EMPTY),
+            reset_low: !R::EMPTY,
+            reset_high: !R::EMPTY,
+            pressed: Cell::new(R::EMPTY),
+            released: Cell::new(R::EMPTY),
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Set the number of consecutive samples needed to acknowledge a change of an input.
+    ///
+    /// ## Parameters
+    /// - `samples`: from 1 to 4 (the capacity of the 2 bits counters)
+    ///
+    /// ## Panics
+    /// If `samples` is not within 1 to 4.
+    ///
+    /// ## Return
+    /// - DebounceBank acknowledging a change after `samples` samples
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        assert!(
+            (1..=BANK_MAX_SAMPLES).contains(&samples),
+            "A debounce bank counts 1 to 4 samples"
+        );
+        // The counters count down and acknowledge the change when rolling over
+        let reset = samples - 1;
+        self.reset_low = if reset & 0b01 != 0 {
+            !R::EMPTY
+        } else {
+            R::EMPTY
+        };
+        self.reset_high = if reset & 0b10 != 0 {
+            !R::EMPTY
+        } else {
+            R::EMPTY
+        };
+        self.count_low.set(self.reset_low);
+        self.count_high.set(self.reset_high);
+        self
+    }
+
+    /// ## Description
+    ///
+    /// Debounce one sample of every input of the bank.
+    ///
+    /// ## Parameters
+    /// - `samples`: raw state of the inputs, one bit per input (set when active)
+    ///
+    /// ## Return
+    /// - `BankEdges`: masks of the inputs which have just been pressed or released
+    pub fn update(&self, samples: R) -> BankEdges<R> {
+        // Inputs differing from their debounced level are counting down.
+        let state = self.state.get();
+        let changing = state ^ samples;
+        let count_low = !(self.count_low.get() & changing);
+        let count_high = count_low ^ (self.count_high.get() & changing);
+
+        // Counters rolling over are the inputs with enough consecutive changed samples.
+        let toggled = changing & count_low & count_high;
+        let state = state ^ toggled;
+        self.state.set(state);
+
+        // The stable and the toggled inputs start counting again from the reset value.
+        let reset = !changing | toggled;
+        self.count_low
+            .set((count_low & !reset) | (self.reset_low & reset));
+        self.count_high
+            .set((count_high & !reset) | (self.reset_high & reset));
+
+        let edges = BankEdges {
+            pressed: toggled & state,
+            released: toggled & !state,
+        };
+        self.pressed.set(self.pressed.get() | edges.pressed);
+        self.released.set(self.released.get() | edges.released);
+        edges
+    }
+
+    /// ## Description
+    ///
+    /// Debounced level of every input of the bank.
+    ///
+    /// ## Return
+    /// - `R`: one bit per input, set when the input is pressed
+    #[inline]
+    pub fn state(&self) -> R {
+        self.state.get()
+    }
+
+    /// ## Description
+    ///
+    /// Debounce state of a single input.
+    ///
+    /// ## Parameters
+    /// - `index`: bit of the input in the bank
+    ///
+    /// ## Panics
+    /// If the bank has no such input.
+    ///
+    /// ## Return
+    /// - `DebounceState`: `Transition` while a change is being confirmed, the debounced level otherwise
+    pub fn get_state(&self, index: u8) -> DebounceState {
+        assert!((index as u32) < R::BITS, "The bank has no such input");
+        let bit = R::bit(index);
+        let counting =
+            (self.count_low.get() ^ self.reset_low) | (self.count_high.get() ^ self.reset_high);
+        if counting & bit != R::EMPTY {
+            DebounceState::Transition
+        } else if self.state.get() & bit != R::EMPTY {
+            DebounceState::Loaded
+        } else {
+            DebounceState::Unloaded
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Consume the pending pressed edge of an input, if any.
+    ///
+    /// ## Panics
+    /// If the bank has no such input.
+    pub fn take_pressed(&self, index: u8) -> bool {
+        assert!((index as u32) < R::BITS, "The bank has no such input");
+        Self::take(&self.pressed, R::bit(index))
+    }
+
+    /// ## Description
+    ///
+    /// Consume the pending released edge of an input, if any.
+    ///
+    /// ## Panics
+    /// If the bank has no such input.
+    pub fn take_released(&self, index: u8) -> bool {
+        assert!((index as u32) < R::BITS, "The bank has no such input");
+        Self::take(&self.released, R::bit(index))
+    }
+
+    #[inline]
+    fn take(edges: &Cell<R>, bit: R) -> bool {
+        let pending = edges.get();
+        edges.set(pending & !bit);
+        pending & bit != R::EMPTY
+    }
+}
+
+impl<R> Default for DebounceBank<R>
+where
+    R: Register,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils;
 
     #[inline(never)]
     #[test]
@@ -95,4 +881,329 @@ mod tests {
         debouncer.debounce(false);
         assert_eq!(DebounceState::Unloaded, debouncer.get_state());
     }
+
+    #[inline(never)]
+    #[test]
+    fn test_debounce_state_from_register() {
+        assert_eq!(DebounceState::Loaded, DebounceState::from(0x07));
+        assert_eq!(DebounceState::Unloaded, DebounceState::from(0x00));
+        assert_eq!(DebounceState::Transition, DebounceState::from(0x03));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_debouncer_custom_samples() {
+        // 32 samples window, 10 ticks to press and 20 ticks to release
+        let mut debouncer = Debouncer::<32>::new(10, 20);
+
+        // Should start unloaded since the register is empty
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+
+        // 9 first ticks are a transition
+        for _ in 0..9 {
+            debouncer.debounce(true);
+            assert_eq!(DebounceState::Transition, debouncer.get_state());
+        }
+
+        // 10th tick reaches loaded state
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+
+        // 19 ticks releasing the switch are a transition
+        for _ in 0..19 {
+            debouncer.debounce(false);
+            assert_eq!(DebounceState::Transition, debouncer.get_state());
+        }
+
+        // 20th tick reaches unloaded state
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_debouncer_bounce_requires_new_release() {
+        // 64 samples window, 2 ticks to press and 4 ticks to release
+        let mut debouncer = Debouncer::<64>::new(2, 4);
+
+        // Bouncing press: active, inactive, active, active
+        debouncer.debounce(true);
+        debouncer.debounce(false);
+        debouncer.debounce(true);
+        // Not enough inactive samples before the press, this is still a transition
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Transition, debouncer.get_state());
+
+        // Release for 4 ticks
+        for _ in 0..4 {
+            debouncer.debounce(false);
+        }
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+
+        // Clean press after a confirmed release
+        debouncer.debounce(true);
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_latching_debouncer() {
+        let mut debouncer = LatchingDebouncer::default();
+
+        // Starts released
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+
+        // 2 first ticks are not enough to latch the press
+        for _ in 0..2 {
+            debouncer.debounce(true);
+            assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+        }
+
+        // The press is latched on the 3rd tick and kept while the input is held
+        for _ in 0..20 {
+            debouncer.debounce(true);
+            assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        }
+
+        // A glitch does not release the latch
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        debouncer.debounce(true);
+
+        // 7 released ticks keep the press latched
+        for _ in 0..7 {
+            debouncer.debounce(false);
+            assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        }
+
+        // The release is latched on the 8th tick
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_integrator_debouncer() {
+        let mut debouncer = IntegratorDebouncer::default();
+
+        // Starts released
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+
+        // Noisy press: the counter goes 1, 0, 1, 2
+        debouncer.debounce(true);
+        debouncer.debounce(false);
+        debouncer.debounce(true);
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+
+        // Upper threshold reached
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+
+        // Isolated glitches during a long press keep the switch pressed
+        for _ in 0..10 {
+            debouncer.debounce(false);
+            assert_eq!(DebounceState::Loaded, debouncer.get_state());
+            debouncer.debounce(true);
+            assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        }
+
+        // Counter saturated at 3, 2 inactive samples are not enough to release
+        for _ in 0..2 {
+            debouncer.debounce(false);
+            assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        }
+
+        // Lower threshold reached
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_integrator_debouncer_hysteresis() {
+        let mut debouncer = IntegratorDebouncer::new(2, 5);
+
+        // Between the thresholds while rising: still released
+        for _ in 0..4 {
+            debouncer.debounce(true);
+        }
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+
+        // Between the thresholds while falling: still pressed
+        debouncer.debounce(false);
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_integrator_debouncer_full_range() {
+        let mut debouncer = IntegratorDebouncer::new(0, u8::MAX);
+
+        for _ in 0..u8::MAX {
+            debouncer.debounce(true);
+        }
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+
+        // Saturates at the upper threshold
+        debouncer.debounce(true);
+        assert_eq!(u8::MAX, debouncer.counter);
+        for _ in 0..u8::MAX - 1 {
+            debouncer.debounce(false);
+        }
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_timed_debouncer() {
+        let clock = test_utils::MockedClock::default();
+        let mut debouncer = TimedDebouncer::new(&clock, 20, 50);
+
+        // Starts released
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+
+        // Fast polling does not change the debounce duration
+        for _ in 0..20 {
+            debouncer.debounce(true);
+            assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+            clock.advance(1);
+        }
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+
+        // The press is kept while the input is held, a single bounce does not release it
+        clock.advance(1000);
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        clock.advance(1);
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+
+        // A bounce during the release restarts the timer
+        debouncer.debounce(false);
+        clock.advance(40);
+        debouncer.debounce(true);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+        debouncer.debounce(false);
+        clock.advance(40);
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Loaded, debouncer.get_state());
+
+        // Slow polling: a single sample after the release duration is enough
+        clock.advance(10);
+        debouncer.debounce(false);
+        assert_eq!(DebounceState::Unloaded, debouncer.get_state());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_debounce_bank() {
+        let bank = DebounceBank::<u16>::new();
+
+        // Input 0 is held, input 9 bounces
+        for samples in [0b10_0000_0001, 0b00_0000_0001, 0b10_0000_0001] {
+            let edges = bank.update(samples);
+            assert_eq!(0, edges.pressed);
+            assert_eq!(DebounceState::Transition, bank.get_state(0));
+        }
+
+        // After 4 samples only input 0 is pressed
+        let edges = bank.update(0b00_0000_0001);
+        assert_eq!(0b00_0000_0001, edges.pressed);
+        assert_eq!(0, edges.released);
+        assert_eq!(0b00_0000_0001, bank.state());
+        assert_eq!(DebounceState::Loaded, bank.get_state(0));
+        assert_eq!(DebounceState::Unloaded, bank.get_state(9));
+
+        // Release input 0 while input 9 is held
+        for _ in 0..3 {
+            assert_eq!(0, bank.update(0b10_0000_0000).released);
+        }
+        let edges = bank.update(0b10_0000_0000);
+        assert_eq!(0b00_0000_0001, edges.released);
+        assert_eq!(0b10_0000_0000, edges.pressed);
+        assert_eq!(DebounceState::Unloaded, bank.get_state(0));
+        assert_eq!(DebounceState::Loaded, bank.get_state(9));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_debounce_bank_samples() {
+        let bank = DebounceBank::<u8>::new().with_samples(2);
+
+        // A single sample is a bounce
+        bank.update(0b01);
+        assert_eq!(DebounceState::Transition, bank.get_state(0));
+        bank.update(0b00);
+        assert_eq!(DebounceState::Unloaded, bank.get_state(0));
+
+        // 2 samples to press, then 2 samples to release
+        bank.update(0b01);
+        assert_eq!(0b01, bank.update(0b01).pressed);
+        assert_eq!(DebounceState::Loaded, bank.get_state(0));
+        assert_eq!(0, bank.update(0b00).released);
+        assert_eq!(0b01, bank.update(0b00).released);
+
+        // Acknowledged on every sample
+        let bank = DebounceBank::<u8>::new().with_samples(1);
+        assert_eq!(0b10, bank.update(0b10).pressed);
+        assert_eq!(0b10, bank.update(0b00).released);
+        assert!(bank.take_pressed(1));
+        assert!(!bank.take_pressed(1));
+    }
+
+    #[inline(never)]
+    #[test]
+    #[should_panic(expected = "The bank has no such input")]
+    fn test_debounce_bank_out_of_range() {
+        let bank = DebounceBank::<u8>::new();
+        bank.get_state(8);
+    }
+
+    #[inline(never)]
+    #[test]
+    #[should_panic]
+    fn test_debouncer_samples_larger_than_window() {
+        let _ = Debouncer::<8>::new(3, 9);
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_debouncer_try_new() {
+        // Valid configurations
+        assert!(Debouncer::<16>::try_new(5, 16).is_ok());
+        assert!(LatchingDebouncer::<8>::try_new(1, 8).is_ok());
+        assert!(IntegratorDebouncer::try_new(1, 4).is_ok());
+
+        // Sample counts must be non null and fit in the window
+        assert_eq!(
+            Some(DebounceError::PressSamples),
+            Debouncer::<8>::try_new(0, 8).err()
+        );
+        assert_eq!(
+            Some(DebounceError::ReleaseSamples),
+            Debouncer::<8>::try_new(3, 9).err()
+        );
+        assert_eq!(
+            Some(DebounceError::PressSamples),
+            LatchingDebouncer::<8>::try_new(9, 1).err()
+        );
+
+        // Thresholds must be ordered
+        assert_eq!(
+            Some(DebounceError::Thresholds),
+            IntegratorDebouncer::try_new(3, 3).err()
+        );
+    }
 }
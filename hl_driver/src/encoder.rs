@@ -1,9 +1,23 @@
-use crate::switch::Pressable;
-use core::fmt::Debug;
-use embedded_hal::digital::InputPin;
+use crate::debounce::{Debounce, DebounceState, LatchingDebouncer};
+use crate::switch::{Pressable, SwitchError, SwitchState};
+use embedded_hal::digital::{Error, ErrorKind, InputPin, PinState};
+
+mod absolute;
+mod acceleration;
+mod as5600;
+mod async_encoder;
+mod position;
+
+pub use absolute::{AbsoluteEncode, GrayCodeEncoder};
+pub use acceleration::{AcceleratedEncoder, AccelerationCurve};
+pub use as5600::{AS5600_ADDRESS, As5600, I2cError, MagnetStatus};
+pub use async_encoder::AsyncEncoder;
+pub use position::{Overflow, Position};
 
 // A valid Rest Direction for a HY040 rotary encoder
 const DEFAULT_STATE: u8 = 0b11;
+// Samples of the glitch filters (u8 register)
+const GLITCH_FILTER_WINDOW: u8 = 8;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 /// ## Description
@@ -12,50 +26,349 @@ pub enum Direction {
     CounterClockwise,
     Clockwise,
     Rest,
+    Invalid, // Both pins changed since the last reading: at least one transition has been missed
+}
+
+/// ## Description
+/// Resolution of the directions reported by an encoder.
+/// The HY040 goes through 4 quadrature transitions per mechanical detent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StepMode {
+    Full, // One direction per detent
+    Half, // One direction per half detent (2 transitions)
+    #[default]
+    Quarter, // One direction per quadrature transition
+}
+
+impl StepMode {
+    /// ## Description
+    /// Number of quadrature transitions per reported direction.
+    #[inline]
+    pub fn transitions(self) -> i8 {
+        match self {
+            StepMode::Full => 4,
+            StepMode::Half => 2,
+            StepMode::Quarter => 1,
+        }
+    }
+
+    /// Indicate if the pins state is aligned with a step, where the transition count is resynchronized.
+    /// The half detents are on the state opposite to the rest state.
+    fn is_aligned(self, pins: u8, rest_state: u8) -> bool {
+        match self {
+            StepMode::Full => pins == rest_state,
+            StepMode::Half => pins == rest_state || pins == rest_state ^ 0b11,
+            StepMode::Quarter => true,
+        }
+    }
+}
+
+/// ## Description
+/// Possible errors related to encoders.
+/// `E` is the error of the underlying inputs, so the root cause of a read failure is not lost.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EncoderError<E> {
+    ReadPinState(E), // The inputs of the encoder could not be read
+    Bus(E),          // The transaction with an encoder on a bus failed
+    NoMagnet,        // The magnet of a magnetic encoder is not detected
+}
+
+impl<E> Error for EncoderError<E>
+where
+    E: Error,
+{
+    fn kind(&self) -> ErrorKind {
+        match self {
+            EncoderError::ReadPinState(e) => e.kind(), // Fwd the pin error kind
+            EncoderError::Bus(e) => e.kind(),
+            EncoderError::NoMagnet => ErrorKind::Other,
+        }
+    }
 }
 
 /// ## Description
 /// Encoder traits.
 pub trait Encode {
+    /// Error of the inputs of the encoder, carried by `EncoderError::ReadPinState` or `EncoderError::Bus`
+    type PinError: Error;
+
     /// ## Description
     /// This function defines a common interface for knowing in what direction an encoder
     /// is being rotated.
     /// ## Return
-    /// Direction in which the encoder is being rotated.
-    fn encode(&mut self) -> Direction;
+    /// *Result<Direction, EncoderError>*
+    /// - `Direction`: direction in which the encoder is being rotated
+    /// - `EncoderError::ReadPinState`: the inputs of the encoder could not be read
+    fn try_encode(&mut self) -> Result<Direction, EncoderError<Self::PinError>>;
+
+    /// ## Description
+    /// Infallible version of `try_encode`, safe to call from an interrupt handler.
+    /// The encoders count their failed readings (e.g. `QuadratureEncoder::read_errors`).
+    /// ## Return
+    /// Direction in which the encoder is being rotated, `Rest` if the inputs could not be read.
+    #[inline]
+    fn encode(&mut self) -> Direction {
+        self.try_encode().unwrap_or(Direction::Rest)
+    }
 }
 
 /// ## Description
-/// Represents a simple Rotary Encoder with basic functionnality.
+/// Incremental quadrature encoder (HY040, EC11, optical encoders...) decoded from its A and B channels.
+///
+/// The encoder is configured with builder methods:
+/// - `with_step_mode`: number of quadrature transitions per reported direction
+/// - `with_active_levels`: polarity of each channel (active-high or active-low)
+/// - `with_rest_state`: channels state at the detents, where partial steps are dropped
+/// - `with_inverted_direction`: swap clockwise and counter clockwise
+/// - `with_position`: bounds of the position tracked from the reported directions
+/// - `with_index`: Z/index input resetting the position once per revolution
+/// - `with_glitch_filter`: ignore the glitches of both channels
+///
+/// The channels state is the logical level of A (bit 1) and B (bit 0), `1` being the active level.
+/// The default configuration is the one of the HY040: active-high channels pulled up at rest (`0b11`).
+///
+/// ## Example
+///
+/// See unit tests for example of use.
 #[derive(Debug)]
-pub struct Hy040<INPUT>
+pub struct QuadratureEncoder<INPUT>
 where
     INPUT: InputPin,
 {
-    clk: INPUT,
-    dt: INPUT,
+    a: INPUT,
+    b: INPUT,
+    index: Option<(INPUT, PinState)>,
+    index_active: bool,
+    index_crossed: bool,
     state: u8,
+    rest_state: u8,
+    // Active-low channels, as a state mask
+    active_low: u8,
+    inverted: bool,
+    step_mode: StepMode,
+    transitions: i8,
+    position: Position,
+    missed_transitions: u32,
+    read_errors: u32,
+    glitch_filter: Option<[LatchingDebouncer; 2]>,
 }
 
-impl<INPUT> Hy040<INPUT>
+/// ## Description
+/// Rotary encoder module HY040 (KY-040): a `QuadratureEncoder` in its default configuration,
+/// with the CLK pin on channel A and the DT pin on channel B.
+pub type Hy040<INPUT> = QuadratureEncoder<INPUT>;
+
+impl<INPUT> QuadratureEncoder<INPUT>
 where
     INPUT: InputPin,
 {
     /// ## Description
     /// Create a new Encoder from which Direction can be retrieved.
+    /// A direction is reported for every quadrature transition (`StepMode::Quarter`).
     /// ### Parameters
-    /// - clk: the gpio pin connected to the A pin of the Rotary encoder
-    /// - dt: the gpio pin connected to the B pin of the Rotary encoder
+    /// - a: the gpio pin connected to the A pin of the encoder (CLK on a HY040)
+    /// - b: the gpio pin connected to the B pin of the encoder (DT on a HY040)
     /// ### Return
     /// - Encoder
-    pub fn new(clk: INPUT, dt: INPUT) -> Self {
-        Hy040 {
-            clk,
-            dt,
+    pub fn new(a: INPUT, b: INPUT) -> Self {
+        QuadratureEncoder {
+            a,
+            b,
+            index: None,
+            index_active: false,
+            index_crossed: false,
             state: DEFAULT_STATE,
+            rest_state: DEFAULT_STATE,
+            active_low: 0,
+            inverted: false,
+            step_mode: StepMode::default(),
+            transitions: 0,
+            position: Position::new(i32::MIN, i32::MAX)
+                .with_overflow(Overflow::Wrap)
+                .with_initial(0),
+            missed_transitions: 0,
+            read_errors: 0,
+            glitch_filter: None,
         }
     }
 
+    /// ## Description
+    /// Set the resolution of the directions reported by the Encoder.
+    /// With `StepMode::Full`, a direction is reported once per mechanical detent.
+    /// ### Parameters
+    /// - step_mode: the resolution of the reported directions
+    /// ### Return
+    /// Encoder reporting directions at the given resolution
+    pub fn with_step_mode(mut self, step_mode: StepMode) -> Self {
+        self.step_mode = step_mode;
+        self.transitions = 0;
+        self
+    }
+
+    /// ## Description
+    /// Set the polarity of the channels: an active-low channel is inverted before decoding.
+    /// ### Parameters
+    /// - a: level of the A pin when the channel is active
+    /// - b: level of the B pin when the channel is active
+    /// ### Return
+    /// Encoder decoding the channels with the given polarity
+    pub fn with_active_levels(mut self, a: PinState, b: PinState) -> Self {
+        self.active_low = 0;
+        if a == PinState::Low {
+            self.active_low |= 0b10;
+        }
+        if b == PinState::Low {
+            self.active_low |= 0b01;
+        }
+        self
+    }
+
+    /// ## Description
+    /// Set the channels state at the detents of the encoder (`0b11` for a HY040, `0b00` for
+    /// encoders pulled to the inactive level at rest).
+    /// The half detents are on the opposite state.
+    /// ### Parameters
+    /// - rest_state: logical state of A (bit 1) and B (bit 0) at the detents
+    /// ### Panics
+    /// If the state does not fit on 2 bits.
+    /// ### Return
+    /// Encoder resynchronizing its steps on the given state
+    pub fn with_rest_state(mut self, rest_state: u8) -> Self {
+        assert!(rest_state <= 0b11, "The rest state is a 2 bits state");
+        self.rest_state = rest_state;
+        self.state = rest_state;
+        self.transitions = 0;
+        self.prime_glitch_filter();
+        self
+    }
+
+    /// ## Description
+    /// Swap the reported directions, for encoders mounted the other way around or wired with A and B
+    /// swapped.
+    /// ### Parameters
+    /// - inverted: `true` to report clockwise rotations as `CounterClockwise`
+    /// ### Return
+    /// Encoder reporting the directions in the given orientation
+    pub fn with_inverted_direction(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    /// ## Description
+    /// Track the position within bounds, e.g. to drive a setting directly from the encoder.
+    /// By default the position is unbounded (wrapping at the limits of an `i32`) and starts at 0.
+    /// ### Parameters
+    /// - position: bounds, overflow behaviour and initial value of the position
+    /// ### Return
+    /// Encoder updating the given position
+    pub fn with_position(mut self, position: Position) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// ## Description
+    /// Add the Z/index input of the encoder, active once per revolution.
+    /// The position is reset to its initial value and the partial step dropped each time the index
+    /// becomes active.
+    /// ### Parameters
+    /// - index: the gpio pin connected to the Z pin of the encoder
+    /// - active: level of the index pin at the index position
+    /// ### Return
+    /// Encoder resetting its position on the index
+    pub fn with_index(mut self, index: INPUT, active: PinState) -> Self {
+        self.index = Some((index, active));
+        self
+    }
+
+    /// ## Description
+    /// Filter the glitches of both pins before decoding the transitions.
+    /// A new level of a pin is only taken into account once it has been read `samples` times in a row,
+    /// so contact bounces and electrical spikes shorter than that are ignored.
+    /// Every edge is delayed by `samples` readings: the encoder has to be read at least `samples`
+    /// times per quadrature transition, otherwise transitions are reported as `Invalid`.
+    /// ### Parameters
+    /// - samples: number of identical readings confirming a level, from 1 to 8
+    /// ### Panics
+    /// If `samples` is 0 or greater than 8.
+    /// ### Return
+    /// Encoder decoding the filtered levels of the pins
+    pub fn with_glitch_filter(mut self, samples: u8) -> Self {
+        self.glitch_filter = Some([
+            LatchingDebouncer::new(samples, samples),
+            LatchingDebouncer::new(samples, samples),
+        ]);
+        self.prime_glitch_filter();
+        self
+    }
+
+    /// ## Description
+    /// Position of the encoder in steps of its `StepMode`, positive clockwise.
+    /// ### Return
+    /// - `i32`: position since the creation, the last reset or the last index, within the bounds
+    ///   given by `with_position`
+    #[inline]
+    pub fn position(&self) -> i32 {
+        self.position.value()
+    }
+
+    /// ## Description
+    /// Move the position back to its initial value.
+    #[inline]
+    pub fn reset_position(&mut self) {
+        self.position.reset();
+    }
+
+    /// ## Description
+    /// Indicate if the index has been crossed since the last time this method has been called.
+    /// ### Return
+    /// - `bool`: `true` if the index became active, always `false` without index input
+    #[inline]
+    pub fn has_crossed_index(&mut self) -> bool {
+        core::mem::take(&mut self.index_crossed)
+    }
+
+    /// ## Description
+    /// Number of invalid transitions seen by the Encoder (both pins changing between two readings).
+    /// A growing count means that the encoder is not read often enough for the rotation speed.
+    /// ### Return
+    /// - `u32`: number of invalid transitions since the creation or the last reset of the counter
+    #[inline]
+    pub fn missed_transitions(&self) -> u32 {
+        self.missed_transitions
+    }
+
+    /// ## Description
+    /// Reset the counter of invalid transitions.
+    #[inline]
+    pub fn reset_missed_transitions(&mut self) {
+        self.missed_transitions = 0;
+    }
+
+    /// ## Description
+    /// Number of readings of the Encoder which failed, including the ones reported as `Rest` by `encode`.
+    /// ### Return
+    /// - `u32`: number of failed readings since the creation or the last reset of the counter
+    #[inline]
+    pub fn read_errors(&self) -> u32 {
+        self.read_errors
+    }
+
+    /// ## Description
+    /// Reset the counter of failed readings.
+    #[inline]
+    pub fn reset_read_errors(&mut self) {
+        self.read_errors = 0;
+    }
+
+    /// ## Description
+    /// Access the pins of the Encoder, e.g. to acknowledge their edge interrupts.
+    /// ### Return
+    /// - `(&mut INPUT, &mut INPUT)`: the A and B pins
+    #[inline]
+    pub fn pins_mut(&mut self) -> (&mut INPUT, &mut INPUT) {
+        (&mut self.a, &mut self.b)
+    }
+
     /// ## Description
     /// Add a switch to an Encoder from which switch status can be read.
     /// The switch has to implement the `Pressable` trait.
@@ -63,38 +376,114 @@ where
     /// - sw: a switch implementing `hl_driver::switch::Pressable`
     /// ### Return
     /// Encoder with switch functionnalities
-    pub fn with_switch<SW: Pressable>(self, sw: SW) -> Hy040WithSwitch<INPUT, SW> {
-        Hy040WithSwitch {
+    pub fn with_switch<SW: Pressable>(self, sw: SW) -> QuadratureEncoderWithSwitch<INPUT, SW> {
+        QuadratureEncoderWithSwitch {
             encoder: self,
             switch: sw,
         }
     }
 }
 
-impl<INPUT> Encode for Hy040<INPUT>
+impl<INPUT> Encode for QuadratureEncoder<INPUT>
 where
     INPUT: InputPin,
 {
+    type PinError = INPUT::Error;
+
     /// ## Description
     /// Read the state of the two pins attached to the rotary forming a 2bits state.
     /// The prior state and the current state are combined in a 4 bits value used
     /// to determine the sense of rotation of the encoder.
+    /// The transitions are then accumulated until a full step of the `StepMode` is reached.
     /// ## Return
-    /// - `Direction`: Direction can be CounterClockwise, Clockwise or Rest.
+    /// *Result<Direction, EncoderError>*
+    /// - `Direction`: Direction can be CounterClockwise, Clockwise, Rest or Invalid (missed transition).
+    /// - `EncoderError::ReadPinState`: one of the pins could not be read, the state of the encoder is kept
     #[inline]
-    fn encode(&mut self) -> Direction {
+    fn try_encode(&mut self) -> Result<Direction, EncoderError<Self::PinError>> {
+        // All the pins are read before updating the state, so a reading error loses no transition
+        let (a, b, index_active) = self.read_inputs().map_err(|e| {
+            self.read_errors = self.read_errors.saturating_add(1);
+            EncoderError::ReadPinState(e)
+        })?;
+
+        let transition = self.transition(a, b);
+        match transition {
+            Direction::Clockwise => self.transitions += 1,
+            Direction::CounterClockwise => self.transitions -= 1,
+            Direction::Rest | Direction::Invalid => (),
+        }
+
+        let step = self.step_mode.transitions();
+        let direction = if transition == Direction::Invalid {
+            self.missed_transitions = self.missed_transitions.saturating_add(1);
+            Direction::Invalid
+        } else if self.transitions >= step {
+            self.transitions -= step;
+            Direction::Clockwise
+        } else if self.transitions <= -step {
+            self.transitions += step;
+            Direction::CounterClockwise
+        } else {
+            Direction::Rest
+        };
+        self.position.update(direction);
+        // Partial steps (bounces, direction changes) are dropped once the encoder settles on a step
+        if self
+            .step_mode
+            .is_aligned(self.state & 0b11, self.rest_state)
+        {
+            self.transitions = 0;
+        }
+
+        if index_active && !self.index_active {
+            self.position.reset();
+            self.transitions = 0;
+            self.index_crossed = true;
+        }
+        self.index_active = index_active;
+        Ok(direction)
+    }
+}
+
+impl<INPUT> QuadratureEncoder<INPUT>
+where
+    INPUT: InputPin,
+{
+    /// ## Description
+    /// Read the A, B and index pins, `true` for an active pin (the index is `false` without index pin).
+    #[inline]
+    fn read_inputs(&mut self) -> Result<(bool, bool, bool), INPUT::Error> {
+        let a = self.a.is_high()? != (self.active_low & 0b10 != 0);
+        let b = self.b.is_high()? != (self.active_low & 0b01 != 0);
+        let index = match &mut self.index {
+            Some((index, active)) => index.is_high()? == bool::from(*active),
+            None => false,
+        };
+        Ok((a, b, index))
+    }
+
+    /// ## Description
+    /// Direction of the last quadrature transition of the pins.
+    #[inline]
+    fn transition(&mut self, mut a: bool, mut b: bool) -> Direction {
+        if let Some([a_filter, b_filter]) = &mut self.glitch_filter {
+            a = filter_level(a_filter, a);
+            b = filter_level(b_filter, b);
+        }
+
         let mut current_state = self.state;
         current_state <<= 2;
-        if self.clk.is_high().expect("Should not fail") {
+        if a {
             current_state |= 0x2
         };
-        if self.dt.is_high().expect("Should not fail") {
+        if b {
             current_state |= 0x1
         };
         current_state &= 0x0F;
         self.state = current_state;
         // Here we have a 4 bits values which represents the last state and the current one
-        match current_state {
+        let direction = match current_state {
             13 => Direction::Clockwise,
             4 => Direction::Clockwise,
             2 => Direction::Clockwise,
@@ -103,36 +492,124 @@ where
             8 => Direction::CounterClockwise,
             1 => Direction::CounterClockwise,
             7 => Direction::CounterClockwise,
+            // Both pins changed
+            3 | 6 | 9 | 12 => Direction::Invalid,
             _ => Direction::Rest,
+        };
+        match (self.inverted, direction) {
+            (true, Direction::Clockwise) => Direction::CounterClockwise,
+            (true, Direction::CounterClockwise) => Direction::Clockwise,
+            _ => direction,
+        }
+    }
+
+    /// ## Description
+    /// Levels of the A and B pins matching the current state (`true` for high).
+    #[inline]
+    fn pin_levels(&self) -> (bool, bool) {
+        let levels = (self.state ^ self.active_low) & 0b11;
+        (levels & 0b10 != 0, levels & 0b01 != 0)
+    }
+
+    /// The glitch filters start on the current state
+    fn prime_glitch_filter(&mut self) {
+        if let Some([a_filter, b_filter]) = &mut self.glitch_filter {
+            // A full window of identical samples latches any sample count
+            for _ in 0..GLITCH_FILTER_WINDOW {
+                a_filter.debounce(self.state & 0b10 != 0);
+                b_filter.debounce(self.state & 0b01 != 0);
+            }
         }
     }
 }
 
+/// Level of a pin once its glitches are filtered out.
+#[inline]
+fn filter_level(filter: &mut LatchingDebouncer, level: bool) -> bool {
+    filter.debounce(level);
+    filter.get_state() == DebounceState::Loaded
+}
+
 /// ## Description
 /// An Encoder with a switch. See hl_driver::switch module for more details.
 /// The encoder implements both the `Encode` trait and the `hl_driver::switch::Pressable` trait.
 #[derive(Debug)]
-pub struct Hy040WithSwitch<INPUT, SW>
+pub struct QuadratureEncoderWithSwitch<INPUT, SW>
 where
     INPUT: InputPin,
     SW: Pressable,
 {
-    encoder: Hy040<INPUT>,
+    encoder: QuadratureEncoder<INPUT>,
     switch: SW,
 }
 
-impl<INPUT, SW> Pressable for Hy040WithSwitch<INPUT, SW>
+/// ## Description
+/// HY040 rotary encoder module with its push button.
+pub type Hy040WithSwitch<INPUT, SW> = QuadratureEncoderWithSwitch<INPUT, SW>;
+
+impl<INPUT, SW> QuadratureEncoderWithSwitch<INPUT, SW>
 where
     INPUT: InputPin,
     SW: Pressable,
 {
+    /// ## Description
+    /// (Forwards `QuadratureEncoder::position`)
+    #[inline]
+    pub fn position(&self) -> i32 {
+        self.encoder.position()
+    }
+
+    /// ## Description
+    /// (Forwards `QuadratureEncoder::reset_position`)
+    #[inline]
+    pub fn reset_position(&mut self) {
+        self.encoder.reset_position()
+    }
+
+    /// ## Description
+    /// (Forwards `QuadratureEncoder::missed_transitions`)
+    #[inline]
+    pub fn missed_transitions(&self) -> u32 {
+        self.encoder.missed_transitions()
+    }
+
+    /// ## Description
+    /// (Forwards `QuadratureEncoder::reset_missed_transitions`)
+    #[inline]
+    pub fn reset_missed_transitions(&mut self) {
+        self.encoder.reset_missed_transitions()
+    }
+
+    /// ## Description
+    /// (Forwards `QuadratureEncoder::read_errors`)
+    #[inline]
+    pub fn read_errors(&self) -> u32 {
+        self.encoder.read_errors()
+    }
+
+    /// ## Description
+    /// (Forwards `QuadratureEncoder::reset_read_errors`)
+    #[inline]
+    pub fn reset_read_errors(&mut self) {
+        self.encoder.reset_read_errors()
+    }
+}
+
+impl<INPUT, SW> Pressable for QuadratureEncoderWithSwitch<INPUT, SW>
+where
+    INPUT: InputPin,
+    SW: Pressable,
+{
+    type PinError = SW::PinError;
+
     /// ## Description
     /// Get the state of the switch when the function is called.
     /// ## Return
     /// - `SwitchState`: Pressed, Released or Transition if debouncer attached to the switch.
+    /// - `SwitchError`: the state of the switch could not be read.
     #[inline]
-    fn get_current_state(&mut self) -> crate::switch::SwitchState {
-        self.switch.get_current_state()
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
+        self.switch.try_get_current_state()
     }
 
     /// ## Description
@@ -141,26 +618,48 @@ where
     /// ## Return
     /// - `bool`: `true` if the switch has been pressed, false otherwise.
     #[inline]
-    fn has_been_pressed(&mut self) -> Result<bool, crate::switch::SwitchError> {
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
         self.switch.has_been_pressed()
     }
+
+    /// ## Description
+    /// Indicate if the switch has been released since the last time this method has been called.
+    /// ## Return
+    /// - `bool`: `true` if the switch has been released, false otherwise.
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        self.switch.has_been_released()
+    }
+
+    /// ## Description
+    /// Duration of the last complete press of the switch.
+    /// ## Return
+    /// - `Option<u64>`: duration in milliseconds, if the switch measures it.
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        self.switch.last_press_duration()
+    }
 }
 
-impl<INPUT, SW> Encode for Hy040WithSwitch<INPUT, SW>
+impl<INPUT, SW> Encode for QuadratureEncoderWithSwitch<INPUT, SW>
 where
     INPUT: InputPin,
     SW: Pressable,
 {
+    type PinError = INPUT::Error;
+
     /// ## Description
     /// (Forwards the `Encode` implementation of the Encoder)
     /// Read the state of the two pins attached to the rotary forming a 2bits state.
     /// The prior state and the current state are combined in a 4 bits value used
     /// to determine the sense of rotation of the encoder.
     /// ## Return
-    /// - `Direction`: Direction can be CounterClockwise, Clockwise or Rest.
+    /// *Result<Direction, EncoderError>*
+    /// - `Direction`: Direction can be CounterClockwise, Clockwise, Rest or Invalid (missed transition).
+    /// - `EncoderError::ReadPinState`: one of the pins could not be read
     #[inline]
-    fn encode(&mut self) -> Direction {
-        self.encoder.encode()
+    fn try_encode(&mut self) -> Result<Direction, EncoderError<Self::PinError>> {
+        self.encoder.try_encode()
     }
 }
 
@@ -254,16 +753,294 @@ mod tests {
         assert_eq!(Direction::Clockwise, dir);
     }
 
+    // Move the encoder through the given pins states (CLK is bit 1, DT is bit 0) and collect the directions
+    fn rotate<const N: usize>(hy040: &mut Hy040<MockedGpioPin>, states: [u8; N]) -> [Direction; N] {
+        states.map(|state| {
+            hy040.a.state = PinState::from(state & 0b10 != 0);
+            hy040.b.state = PinState::from(state & 0b01 != 0);
+            hy040.encode()
+        })
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_encoder_full_step_mode() {
+        let mut hy040 = Hy040::new(MockedGpioPin::default(), MockedGpioPin::default())
+            .with_step_mode(StepMode::Full);
+        let rest = Direction::Rest;
+
+        // One direction per detent, on reaching the next detent
+        let directions = rotate(&mut hy040, [0b01, 0b00, 0b10, 0b11]);
+        assert_eq!([rest, rest, rest, Direction::Clockwise], directions);
+        let directions = rotate(&mut hy040, [0b10, 0b00, 0b01, 0b11]);
+        assert_eq!([rest, rest, rest, Direction::CounterClockwise], directions);
+
+        // Going back to the detent before reaching the next one is not a step
+        let directions = rotate(&mut hy040, [0b01, 0b00, 0b01, 0b11]);
+        assert_eq!([rest; 4], directions);
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_encoder_half_step_mode() {
+        let mut hy040 = Hy040::new(MockedGpioPin::default(), MockedGpioPin::default())
+            .with_step_mode(StepMode::Half);
+        let rest = Direction::Rest;
+
+        let directions = rotate(&mut hy040, [0b01, 0b00, 0b10, 0b11]);
+        assert_eq!(
+            [rest, Direction::Clockwise, rest, Direction::Clockwise],
+            directions
+        );
+        // A bounce on the way is absorbed
+        let directions = rotate(&mut hy040, [0b10, 0b11, 0b10, 0b00]);
+        assert_eq!([rest, rest, rest, Direction::CounterClockwise], directions);
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_encoder_missed_transitions() {
+        let mut hy040 = Hy040::new(MockedGpioPin::default(), MockedGpioPin::default());
+
+        // 11 -> 00 -> 11: both pins change at each reading
+        let directions = rotate(&mut hy040, [0b00, 0b11, 0b01]);
+        assert_eq!(
+            [Direction::Invalid, Direction::Invalid, Direction::Clockwise],
+            directions
+        );
+        assert_eq!(2, hy040.missed_transitions());
+
+        hy040.reset_missed_transitions();
+        assert_eq!(0, hy040.missed_transitions());
+    }
+
+    // Clockwise rotation over `N / 24` detents, every edge bounces back once before settling
+    fn noisy_clockwise<const N: usize>() -> [u8; N] {
+        const CYCLE: [u8; 4] = [0b01, 0b00, 0b10, 0b11];
+        core::array::from_fn(|sample| {
+            let transition = sample / 6;
+            if sample % 6 == 1 {
+                CYCLE[(transition + 3) % 4]
+            } else {
+                CYCLE[transition % 4]
+            }
+        })
+    }
+
+    // Net number of steps, positive clockwise
+    fn net_steps(directions: &[Direction]) -> i32 {
+        directions
+            .iter()
+            .map(|direction| match direction {
+                Direction::Clockwise => 1,
+                Direction::CounterClockwise => -1,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_encoder_glitch_filter_bounces() {
+        let waveform = noisy_clockwise::<72>();
+        let is_ccw = |direction: &Direction| *direction == Direction::CounterClockwise;
+
+        // Without filter, every bounce is decoded as a step back and forth
+        let mut hy040 = Hy040::new(MockedGpioPin::default(), MockedGpioPin::default());
+        let directions = rotate(&mut hy040, waveform);
+        assert_eq!(12, directions.iter().filter(|d| is_ccw(d)).count());
+
+        // Filtered: only the 12 quarter steps of the 3 detents
+        let mut hy040 =
+            Hy040::new(MockedGpioPin::default(), MockedGpioPin::default()).with_glitch_filter(3);
+        let directions = rotate(&mut hy040, waveform);
+        assert!(!directions.iter().any(is_ccw));
+        assert_eq!(12, net_steps(&directions));
+
+        // One step per detent in full step mode
+        let mut hy040 = Hy040::new(MockedGpioPin::default(), MockedGpioPin::default())
+            .with_glitch_filter(3)
+            .with_step_mode(StepMode::Full);
+        let directions = rotate(&mut hy040, waveform);
+        assert_eq!(3, net_steps(&directions));
+        assert_eq!(0, hy040.missed_transitions());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_encoder_glitch_filter_spikes() {
+        // Single reading spikes at rest, on one pin then on both
+        let spikes = [0b11, 0b01, 0b11, 0b11, 0b10, 0b11, 0b00, 0b11, 0b11];
+
+        let mut hy040 = Hy040::new(MockedGpioPin::default(), MockedGpioPin::default());
+        rotate(&mut hy040, spikes);
+        assert_eq!(2, hy040.missed_transitions());
+
+        let mut hy040 =
+            Hy040::new(MockedGpioPin::default(), MockedGpioPin::default()).with_glitch_filter(2);
+        assert_eq!([Direction::Rest; 9], rotate(&mut hy040, spikes));
+        assert_eq!(0, hy040.missed_transitions());
+
+        // A level held long enough goes through
+        let directions = rotate(&mut hy040, [0b01, 0b01, 0b01]);
+        assert_eq!(
+            [Direction::Rest, Direction::Clockwise, Direction::Rest],
+            directions
+        );
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_quadrature_polarity_and_rest_state() {
+        // Active-low channels pulled up: the pins rest high, the channels rest inactive
+        let mut encoder =
+            QuadratureEncoder::new(MockedGpioPin::default(), MockedGpioPin::default())
+                .with_active_levels(PinState::Low, PinState::Low)
+                .with_rest_state(0b00)
+                .with_step_mode(StepMode::Full);
+        let rest = Direction::Rest;
+
+        let directions = rotate(&mut encoder, [0b11, 0b01, 0b00, 0b10, 0b11]);
+        assert_eq!([rest, rest, rest, rest, Direction::Clockwise], directions);
+        assert_eq!(1, encoder.position());
+
+        // Active-high channels resting low (optical encoder), stopping on a half detent is not a step
+        let mut encoder =
+            QuadratureEncoder::new(MockedGpioPin::default(), MockedGpioPin::default())
+                .with_rest_state(0b00)
+                .with_step_mode(StepMode::Full);
+        let directions = rotate(&mut encoder, [0b00, 0b01, 0b11, 0b01, 0b00]);
+        assert_eq!([rest; 5], directions);
+        let directions = rotate(&mut encoder, [0b01, 0b11, 0b10, 0b00]);
+        assert_eq!([rest, rest, rest, Direction::CounterClockwise], directions);
+        assert_eq!(-1, encoder.position());
+        assert_eq!(0, encoder.missed_transitions());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_quadrature_bounded_position() {
+        let mut encoder =
+            QuadratureEncoder::new(MockedGpioPin::default(), MockedGpioPin::default())
+                .with_step_mode(StepMode::Full)
+                .with_position(Position::new(0, 1).with_initial(1));
+
+        // Clamped on the upper bound
+        rotate(&mut encoder, [0b01, 0b00, 0b10, 0b11]);
+        assert_eq!(1, encoder.position());
+        rotate(&mut encoder, [0b10, 0b00, 0b01, 0b11]);
+        assert_eq!(0, encoder.position());
+        rotate(&mut encoder, [0b10, 0b00, 0b01, 0b11]);
+        assert_eq!(0, encoder.position());
+
+        // Back to the initial value
+        encoder.reset_position();
+        assert_eq!(1, encoder.position());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_quadrature_inverted_direction() {
+        let mut hy040 = Hy040::new(MockedGpioPin::default(), MockedGpioPin::default())
+            .with_inverted_direction(true)
+            .with_step_mode(StepMode::Full);
+        let rest = Direction::Rest;
+
+        let directions = rotate(&mut hy040, [0b01, 0b00, 0b10, 0b11]);
+        assert_eq!([rest, rest, rest, Direction::CounterClockwise], directions);
+        assert_eq!(-1, hy040.position());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_quadrature_index() {
+        let mut encoder =
+            QuadratureEncoder::new(MockedGpioPin::default(), MockedGpioPin::default())
+                .with_index(MockedGpioPin::default(), PinState::High);
+        let set_index = |encoder: &mut QuadratureEncoder<MockedGpioPin>, state| {
+            if let Some((index, _)) = encoder.index.as_mut() {
+                index.state = state;
+            }
+        };
+
+        rotate(&mut encoder, [0b11, 0b01, 0b00, 0b10]);
+        assert_eq!(3, encoder.position());
+        assert!(!encoder.has_crossed_index());
+
+        // The index resets the position once when it becomes active
+        set_index(&mut encoder, PinState::High);
+        assert_eq!([Direction::Clockwise], rotate(&mut encoder, [0b11]));
+        assert_eq!(0, encoder.position());
+        assert!(encoder.has_crossed_index());
+        assert!(!encoder.has_crossed_index());
+        rotate(&mut encoder, [0b01]);
+        assert_eq!(1, encoder.position());
+
+        // Next revolution
+        set_index(&mut encoder, PinState::Low);
+        rotate(&mut encoder, [0b00, 0b10]);
+        set_index(&mut encoder, PinState::High);
+        rotate(&mut encoder, [0b11]);
+        assert_eq!(0, encoder.position());
+        assert!(encoder.has_crossed_index());
+
+        // An error on the index pin loses neither the step nor the index
+        set_index(&mut encoder, PinState::Low);
+        rotate(&mut encoder, [0b01, 0b00, 0b10]);
+        set_index(&mut encoder, PinState::High);
+        encoder.b.state = PinState::High;
+        if let Some((index, _)) = encoder.index.as_mut() {
+            index.fault = true;
+        }
+        assert_eq!(
+            Err(EncoderError::ReadPinState(ErrorKind::Other)),
+            encoder.try_encode()
+        );
+        if let Some((index, _)) = encoder.index.as_mut() {
+            index.fault = false;
+        }
+        assert_eq!(Ok(Direction::Clockwise), encoder.try_encode());
+        assert_eq!(0, encoder.position());
+        assert!(encoder.has_crossed_index());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_encoder_read_error() {
+        let mut hy040 = Hy040::new(MockedGpioPin::default(), MockedGpioPin::default()).with_switch(
+            crate::switch::Switch::new(MockedGpioPin::default(), PinState::Low),
+        );
+
+        // The error is reported and the state of the encoder is kept
+        hy040.encoder.b.fault = true;
+        assert_eq!(
+            Err(EncoderError::ReadPinState(ErrorKind::Other)),
+            hy040.try_encode()
+        );
+        assert_eq!(Direction::Rest, hy040.encode());
+        assert_eq!(DEFAULT_STATE, hy040.encoder.state);
+        // Both failed readings are counted
+        assert_eq!(2, hy040.read_errors());
+
+        // Back to normal: 11 -> 01
+        hy040.encoder.b.fault = false;
+        hy040.encoder.b.state = PinState::High;
+        assert_eq!(Ok(Direction::Clockwise), hy040.try_encode());
+        assert_eq!(2, hy040.read_errors());
+        hy040.reset_read_errors();
+        assert_eq!(0, hy040.read_errors());
+    }
+
     #[inline(never)]
     #[test]
     fn test_encoder_should_return_rest_dir() {
-        // Both pin are initiated low and non faulty
+        // Both pin are initiated high (default state) and non faulty
         let mocked_clk_pin = MockedGpioPin {
-            state: PinState::Low,
+            state: PinState::High,
             fault: false,
         };
         let mocked_dt_pin = MockedGpioPin {
-            state: PinState::Low,
+            state: PinState::High,
             fault: false,
         };
 
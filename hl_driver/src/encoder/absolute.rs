@@ -0,0 +1,302 @@
+use embedded_hal::digital::{InputPin, PinState};
+
+use crate::encoder::{Direction, Encode, EncoderError};
+
+// Millidegrees in a turn
+const TURN_MDEG: u32 = 360_000;
+// Gray codes up to 16 bits, so positions fit in a u16
+const MAX_GRAY_BITS: usize = 16;
+
+/// ## Description
+/// Encoders reporting their absolute position within a turn.
+/// The `Encode` implementation of an absolute encoder reports the steps between successive readings.
+pub trait AbsoluteEncode: Encode {
+    /// ## Description
+    /// Number of positions in a turn.
+    fn resolution(&self) -> u32;
+
+    /// ## Description
+    /// Read the absolute position of the encoder.
+    /// ### Return
+    /// *Result<u16, EncoderError>*
+    /// - `u16`: position, from 0 to `resolution() - 1`, increasing clockwise
+    /// - `EncoderError`: the encoder could not be read
+    fn try_position(&mut self) -> Result<u16, EncoderError<Self::PinError>>;
+
+    /// ## Description
+    /// Read the absolute angle of the encoder.
+    /// ### Return
+    /// *Result<u32, EncoderError>*
+    /// - `u32`: angle in millidegrees, from 0 to 359 999, increasing clockwise
+    /// - `EncoderError`: the encoder could not be read
+    #[inline]
+    fn try_angle_mdeg(&mut self) -> Result<u32, EncoderError<Self::PinError>> {
+        let position = self.try_position()? as u64;
+        Ok((position * TURN_MDEG as u64 / self.resolution() as u64) as u32)
+    }
+}
+
+/// ## Description
+/// Steps between the successive readings of an absolute encoder.
+/// The movement between two readings is the shortest one, across the 0 position if needed,
+/// so the encoder has to be read at least twice per half turn.
+#[derive(Debug)]
+pub(super) struct StepCounter {
+    resolution: u32,
+    step_size: u32,
+    last: Option<u32>,
+    pending: i32,
+}
+
+impl StepCounter {
+    /// Panics if the step is empty or larger than half a turn.
+    pub(super) fn new(resolution: u32, step_size: u32) -> Self {
+        assert!(
+            step_size > 0 && step_size <= resolution / 2,
+            "The step size must be within half a turn"
+        );
+        StepCounter {
+            resolution,
+            step_size,
+            last: None,
+            pending: 0,
+        }
+    }
+
+    /// Accumulate the movement since the last position, one step is reported per update.
+    pub(super) fn update(&mut self, position: u32) -> Direction {
+        if let Some(last) = self.last.replace(position) {
+            let resolution = self.resolution as i32;
+            let mut delta = (position as i32 - last as i32).rem_euclid(resolution);
+            if delta > resolution / 2 {
+                delta -= resolution;
+            }
+            self.pending = self.pending.saturating_add(delta);
+        }
+
+        let step = self.step_size as i32;
+        if self.pending >= step {
+            self.pending -= step;
+            Direction::Clockwise
+        } else if self.pending <= -step {
+            self.pending += step;
+            Direction::CounterClockwise
+        } else {
+            Direction::Rest
+        }
+    }
+}
+
+/// ## Description
+/// Absolute encoder giving its position as a `N` bits Gray code, read from `N` pins.
+/// Only one bit changes between two adjacent positions, so a reading during a transition is
+/// always one of the two positions.
+///
+/// The pins are ordered from the least significant bit, a direction is reported per position.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct GrayCodeEncoder<INPUT, const N: usize>
+where
+    INPUT: InputPin,
+{
+    pins: [INPUT; N],
+    active: PinState,
+    steps: StepCounter,
+    read_errors: u32,
+}
+
+impl<INPUT, const N: usize> GrayCodeEncoder<INPUT, N>
+where
+    INPUT: InputPin,
+{
+    /// ## Description
+    /// Create a Gray code encoder with active-high bits.
+    /// ### Parameters
+    /// - pins: the gpio pins connected to the bits of the encoder, least significant bit first
+    /// ### Panics
+    /// If the encoder has less than 2 or more than 16 bits.
+    /// ### Return
+    /// - GrayCodeEncoder
+    pub fn new(pins: [INPUT; N]) -> Self {
+        assert!(
+            (2..=MAX_GRAY_BITS).contains(&N),
+            "A Gray code encoder has 2 to 16 bits"
+        );
+        GrayCodeEncoder {
+            pins,
+            active: PinState::High,
+            steps: StepCounter::new(1 << N, 1),
+            read_errors: 0,
+        }
+    }
+
+    /// ## Description
+    /// Set the level of the pins for the bits at 1 (`PinState::Low` for contacts pulling the pins down).
+    /// ### Parameters
+    /// - active: level of a pin when its bit is set
+    /// ### Return
+    /// Encoder reading its bits with the given polarity
+    pub fn with_active_level(mut self, active: PinState) -> Self {
+        self.active = active;
+        self
+    }
+
+    /// ## Description
+    /// Set the number of positions per reported direction.
+    /// ### Parameters
+    /// - positions: positions per step
+    /// ### Panics
+    /// If `positions` is 0 or larger than half a turn.
+    /// ### Return
+    /// Encoder reporting a direction every `positions` positions
+    pub fn with_step_size(mut self, positions: u16) -> Self {
+        self.steps = StepCounter::new(1 << N, positions as u32);
+        self
+    }
+
+    /// ## Description
+    /// Number of readings of the encoder which failed, including the ones reported as `Rest` by `encode`.
+    /// ### Return
+    /// - `u32`: number of failed readings since the creation or the last reset of the counter
+    #[inline]
+    pub fn read_errors(&self) -> u32 {
+        self.read_errors
+    }
+
+    /// ## Description
+    /// Reset the counter of failed readings.
+    #[inline]
+    pub fn reset_read_errors(&mut self) {
+        self.read_errors = 0;
+    }
+}
+
+impl<INPUT, const N: usize> AbsoluteEncode for GrayCodeEncoder<INPUT, N>
+where
+    INPUT: InputPin,
+{
+    #[inline]
+    fn resolution(&self) -> u32 {
+        1 << N
+    }
+
+    /// ## Description
+    /// Read the Gray code on the pins and convert it to a binary position.
+    #[inline]
+    fn try_position(&mut self) -> Result<u16, EncoderError<Self::PinError>> {
+        let mut gray = 0;
+        for (bit, pin) in self.pins.iter_mut().enumerate() {
+            let high = pin.is_high().map_err(EncoderError::ReadPinState)?;
+            if high == bool::from(self.active) {
+                gray |= 1 << bit;
+            }
+        }
+        // Each binary bit is the xor of the Gray bits above it
+        let mut position: u16 = gray;
+        let mut shifted = gray >> 1;
+        while shifted != 0 {
+            position ^= shifted;
+            shifted >>= 1;
+        }
+        Ok(position)
+    }
+}
+
+impl<INPUT, const N: usize> Encode for GrayCodeEncoder<INPUT, N>
+where
+    INPUT: InputPin,
+{
+    type PinError = INPUT::Error;
+
+    /// ## Description
+    /// Read the position and report the movement since the last reading.
+    /// ## Return
+    /// *Result<Direction, EncoderError>*
+    /// - `Direction`: CounterClockwise, Clockwise or Rest (the first reading is always at rest)
+    /// - `EncoderError::ReadPinState`: one of the pins could not be read
+    #[inline]
+    fn try_encode(&mut self) -> Result<Direction, EncoderError<Self::PinError>> {
+        let position = self
+            .try_position()
+            .inspect_err(|_| self.read_errors = self.read_errors.saturating_add(1))?;
+        Ok(self.steps.update(position as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockedGpioPin;
+
+    // Set the pins of the encoder to the Gray code of the position
+    fn set_position<const N: usize>(
+        encoder: &mut GrayCodeEncoder<MockedGpioPin, N>,
+        position: u16,
+    ) {
+        let gray = position ^ (position >> 1);
+        for (bit, pin) in encoder.pins.iter_mut().enumerate() {
+            pin.state = PinState::from(gray & 1 << bit != 0);
+        }
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_gray_code_position() {
+        let mut encoder = GrayCodeEncoder::new(core::array::from_fn::<_, 4, _>(|_| {
+            MockedGpioPin::default()
+        }));
+
+        for position in 0..16 {
+            set_position(&mut encoder, position);
+            assert_eq!(Ok(position), encoder.try_position());
+        }
+        set_position(&mut encoder, 4);
+        assert_eq!(Ok(90_000), encoder.try_angle_mdeg());
+
+        // Active-low bits: all pins low is the Gray code 1111
+        let mut encoder = encoder.with_active_level(PinState::Low);
+        set_position(&mut encoder, 0);
+        assert_eq!(Ok(10), encoder.try_position());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_gray_code_directions() {
+        let mut encoder = GrayCodeEncoder::new(core::array::from_fn::<_, 4, _>(|_| {
+            MockedGpioPin::default()
+        }));
+        let cw = Direction::Clockwise;
+        let ccw = Direction::CounterClockwise;
+
+        let directions = [14, 15, 0, 1, 1, 0].map(|position| {
+            set_position(&mut encoder, position);
+            encoder.encode()
+        });
+        // Across the 0 position
+        assert_eq!(
+            [Direction::Rest, cw, cw, cw, Direction::Rest, ccw],
+            directions
+        );
+
+        // Skipped positions are reported on the next readings
+        set_position(&mut encoder, 3);
+        assert_eq!(
+            [cw, cw, cw, Direction::Rest],
+            [(); 4].map(|_| encoder.encode())
+        );
+
+        // Reading errors are reported
+        encoder.pins[2].fault = true;
+        assert_eq!(
+            Err(EncoderError::ReadPinState(
+                embedded_hal::digital::ErrorKind::Other
+            )),
+            encoder.try_encode()
+        );
+        assert_eq!(Direction::Rest, encoder.encode());
+        assert_eq!(2, encoder.read_errors());
+    }
+}
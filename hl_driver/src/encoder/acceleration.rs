@@ -0,0 +1,228 @@
+use crate::encoder::{Direction, Encode, EncoderError};
+use crate::time::Monotonic;
+
+// Default acceleration curve
+const DEFAULT_SLOW_MS: u32 = 150;
+const DEFAULT_FAST_MS: u32 = 20;
+const DEFAULT_MAX_MULTIPLIER: u8 = 8;
+
+/// ## Description
+/// Step multiplier applied depending on the time elapsed since the previous step, in milliseconds.
+/// The multiplier is 1 for steps slower than `slow_ms`, `max_multiplier` for steps faster than
+/// `fast_ms`, and grows linearly in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccelerationCurve {
+    /// Interval above which no acceleration is applied
+    pub slow_ms: u32,
+    /// Interval below which the maximum acceleration is applied
+    pub fast_ms: u32,
+    /// Multiplier of the fastest steps
+    pub max_multiplier: u8,
+}
+
+impl Default for AccelerationCurve {
+    fn default() -> Self {
+        AccelerationCurve {
+            slow_ms: DEFAULT_SLOW_MS,
+            fast_ms: DEFAULT_FAST_MS,
+            max_multiplier: DEFAULT_MAX_MULTIPLIER,
+        }
+    }
+}
+
+impl AccelerationCurve {
+    /// ## Description
+    /// Multiplier of a step happening `interval_ms` after the previous one.
+    pub fn multiplier(&self, interval_ms: u64) -> u8 {
+        let max = self.max_multiplier.max(1);
+        if interval_ms >= self.slow_ms as u64 {
+            1
+        } else if interval_ms <= self.fast_ms as u64 {
+            max
+        } else {
+            // Linear interpolation between (slow_ms, 1) and (fast_ms, max)
+            let span = (self.slow_ms - self.fast_ms) as u64;
+            let speed = self.slow_ms as u64 - interval_ms;
+            1 + ((max as u64 - 1) * speed / span) as u8
+        }
+    }
+}
+
+/// ## Description
+/// Acceleration layer over any encoder implementing the `Encode` trait.
+/// Fast rotations move by several steps per detent along an `AccelerationCurve`,
+/// so large ranges can be scrolled quickly while slow rotations stay precise.
+///
+/// Changing the direction of rotation cancels the acceleration.
+/// The encoder is best used in `StepMode::Full`, so the intervals are measured between detents.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct AcceleratedEncoder<E, C>
+where
+    E: Encode,
+    C: Monotonic,
+{
+    encoder: E,
+    clock: C,
+    curve: AccelerationCurve,
+    last_direction: Direction,
+    last_step_at: u64,
+}
+
+impl<E, C> AcceleratedEncoder<E, C>
+where
+    E: Encode,
+    C: Monotonic,
+{
+    /// ## Description
+    /// Add acceleration to an encoder.
+    /// ### Parameters
+    /// - encoder: an encoder implementing `hl_driver::encoder::Encode`
+    /// - clock: a clock implementing `hl_driver::time::Monotonic`
+    /// - curve: the multiplier applied depending on the rotation speed
+    /// ### Return
+    /// - AcceleratedEncoder
+    pub fn new(encoder: E, clock: C, curve: AccelerationCurve) -> Self {
+        AcceleratedEncoder {
+            encoder,
+            clock,
+            curve,
+            last_direction: Direction::Rest,
+            last_step_at: 0,
+        }
+    }
+
+    /// ## Description
+    /// Access the accelerated encoder (e.g. for its diagnostics).
+    #[inline]
+    pub fn encoder(&self) -> &E {
+        &self.encoder
+    }
+
+    /// ## Description
+    /// Mutable access to the accelerated encoder (e.g. to service its pins).
+    #[inline]
+    pub fn encoder_mut(&mut self) -> &mut E {
+        &mut self.encoder
+    }
+
+    /// ## Description
+    /// Give back the encoder.
+    pub fn into_inner(self) -> E {
+        self.encoder
+    }
+
+    /// ## Description
+    /// Read the encoder and apply the acceleration.
+    /// Has to be called as often as the wrapped encoder `encode` method.
+    /// ### Return
+    /// - `i32`: number of steps, positive clockwise, negative counter clockwise, 0 at rest
+    ///   (or if the encoder could not be read)
+    #[inline]
+    pub fn poll(&mut self) -> i32 {
+        self.try_poll().unwrap_or(0)
+    }
+
+    /// ## Description
+    /// Fallible version of `poll`.
+    /// ### Return
+    /// *Result<i32, EncoderError>*
+    /// - `i32`: number of steps, positive clockwise, negative counter clockwise, 0 at rest
+    /// - `EncoderError`: the error reported by the encoder
+    pub fn try_poll(&mut self) -> Result<i32, EncoderError<E::PinError>> {
+        let direction = self.encoder.try_encode()?;
+        let sign = match direction {
+            Direction::Clockwise => 1,
+            Direction::CounterClockwise => -1,
+            Direction::Rest | Direction::Invalid => return Ok(0),
+        };
+
+        let now = self.clock.now_ms();
+        let multiplier = if direction == self.last_direction {
+            self.curve.multiplier(now - self.last_step_at)
+        } else {
+            1
+        };
+        self.last_direction = direction;
+        self.last_step_at = now;
+        Ok(sign * multiplier as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::Position;
+    use crate::test_utils::{MockedClock, MockedEncoder};
+
+    const CW: Direction = Direction::Clockwise;
+    const CCW: Direction = Direction::CounterClockwise;
+
+    // Play the rotation script, with `interval_ms` between the detents
+    fn play<const N: usize>(
+        encoder: &mut AcceleratedEncoder<MockedEncoder<'_>, &MockedClock>,
+        clock: &MockedClock,
+        interval_ms: u64,
+    ) -> [i32; N] {
+        core::array::from_fn(|_| {
+            clock.advance(interval_ms);
+            encoder.poll()
+        })
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_acceleration_curve() {
+        let curve = AccelerationCurve::default();
+
+        assert_eq!(1, curve.multiplier(500));
+        assert_eq!(1, curve.multiplier(150));
+        assert_eq!(4, curve.multiplier(85));
+        assert_eq!(8, curve.multiplier(20));
+        assert_eq!(8, curve.multiplier(0));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_scripted_rotation() {
+        let clock = MockedClock::default();
+        let script = [CW, CW, CW, CW, CW, CW, CCW, CCW, Direction::Rest];
+        let mut encoder = AcceleratedEncoder::new(
+            MockedEncoder::new(&script),
+            &clock,
+            AccelerationCurve::default(),
+        );
+
+        // Slow detents are not accelerated
+        assert_eq!([1, 1, 1], play(&mut encoder, &clock, 200));
+        // Fast detents are
+        assert_eq!([8, 8, 8], play(&mut encoder, &clock, 10));
+        // Turning back starts slowly again
+        assert_eq!([-1, -8], play(&mut encoder, &clock, 10));
+        // Nothing at rest
+        assert_eq!([0, 0], play(&mut encoder, &clock, 10));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_accelerated_position() {
+        let clock = MockedClock::default();
+        let script = [CW; 20];
+        let mut encoder = AcceleratedEncoder::new(
+            MockedEncoder::new(&script),
+            &clock,
+            AccelerationCurve::default(),
+        );
+        let mut position = Position::new(0, 120);
+
+        // A quick spin covers the whole range in a few detents
+        for _ in 0..20 {
+            clock.advance(15);
+            position.step(encoder.poll());
+        }
+        assert_eq!(120, position.value());
+    }
+}
@@ -0,0 +1,285 @@
+use embedded_hal::digital::{self, ErrorKind};
+use embedded_hal::i2c::{self, I2c};
+
+use crate::encoder::absolute::{AbsoluteEncode, StepCounter};
+use crate::encoder::{Direction, Encode, EncoderError};
+
+/// I2C address of the AS5600 (fixed)
+pub const AS5600_ADDRESS: u8 = 0x36;
+// STATUS register, followed by RAW ANGLE (2 bytes) and ANGLE (2 bytes)
+const AS5600_STATUS: u8 = 0x0B;
+const STATUS_MAGNET_DETECTED: u8 = 1 << 5;
+const STATUS_MAGNET_TOO_WEAK: u8 = 1 << 4;
+const STATUS_MAGNET_TOO_STRONG: u8 = 1 << 3;
+// 12 bits angle
+const AS5600_RESOLUTION: u32 = 4096;
+// 32 steps per turn
+const DEFAULT_STEP_SIZE: u16 = 128;
+
+/// ## Description
+/// Position of the magnet above an AS5600, as reported by its STATUS register.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MagnetStatus {
+    Detected,  // The magnet is in the working range
+    TooWeak,   // Detected, but too far from the sensor (maximum gain)
+    TooStrong, // Detected, but too close to the sensor (minimum gain)
+    Missing,   // No magnet, the angle is meaningless
+}
+
+/// ## Description
+/// Error of the I2C bus of an `As5600`.
+/// Wraps the error of the bus so it can be carried by `EncoderError::Bus`, like the errors of the
+/// input pins of the other encoders.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct I2cError<E>(pub E);
+
+impl<E> digital::Error for I2cError<E>
+where
+    E: i2c::Error,
+{
+    /// ## Description
+    /// `embedded_hal::digital::ErrorKind` has no other kind than `Other`, so every bus error maps
+    /// to it. The kind of the bus error is given by `I2cError::bus_kind`.
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl<E> I2cError<E>
+where
+    E: i2c::Error,
+{
+    /// ## Description
+    /// Kind of the wrapped I2C error.
+    /// ### Return
+    /// - `i2c::ErrorKind`: kind of the bus error (e.g. `NoAcknowledge` if the sensor is not connected)
+    #[inline]
+    pub fn bus_kind(&self) -> i2c::ErrorKind {
+        self.0.kind()
+    }
+}
+
+/// ## Description
+/// AS5600 contactless magnetic angle sensor on an I2C bus, used as a knob that never wears out.
+///
+/// The 12 bits `ANGLE` output of the sensor is read, so the zero position programmed in the sensor
+/// (`ZPOS`) is taken into account. The status of the magnet is checked on every reading, in the same
+/// transaction as the angle.
+///
+/// Only full-circle configurations are supported: the angular range (`MPOS`, `MANG`) must be left to
+/// its default of a full turn. With a limited range, `ANGLE` is scaled over that range and does not
+/// wrap, so a jump from one end of the range to the other would be counted as a short movement
+/// across the 0 position.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct As5600<I2C>
+where
+    I2C: I2c,
+{
+    i2c: I2C,
+    steps: StepCounter,
+    read_errors: u32,
+}
+
+impl<I2C> As5600<I2C>
+where
+    I2C: I2c,
+{
+    /// ## Description
+    /// Create an AS5600 reporting 32 steps per turn.
+    /// ### Parameters
+    /// - i2c: the I2C bus of the sensor, implementing `embedded_hal::i2c::I2c`
+    /// ### Return
+    /// - As5600
+    pub fn new(i2c: I2C) -> Self {
+        As5600 {
+            i2c,
+            steps: StepCounter::new(AS5600_RESOLUTION, DEFAULT_STEP_SIZE as u32),
+            read_errors: 0,
+        }
+    }
+
+    /// ## Description
+    /// Set the angle per reported direction, in 1/4096 of a turn.
+    /// ### Parameters
+    /// - positions: positions per step
+    /// ### Panics
+    /// If `positions` is 0 or larger than half a turn.
+    /// ### Return
+    /// Sensor reporting a direction every `positions` positions
+    pub fn with_step_size(mut self, positions: u16) -> Self {
+        self.steps = StepCounter::new(AS5600_RESOLUTION, positions as u32);
+        self
+    }
+
+    /// ## Description
+    /// Number of readings of the sensor which failed (bus error or missing magnet), including the
+    /// ones reported as `Rest` by `encode`.
+    /// ### Return
+    /// - `u32`: number of failed readings since the creation or the last reset of the counter
+    #[inline]
+    pub fn read_errors(&self) -> u32 {
+        self.read_errors
+    }
+
+    /// ## Description
+    /// Reset the counter of failed readings.
+    #[inline]
+    pub fn reset_read_errors(&mut self) {
+        self.read_errors = 0;
+    }
+
+    /// ## Description
+    /// Give back the I2C bus.
+    pub fn into_inner(self) -> I2C {
+        self.i2c
+    }
+
+    /// ## Description
+    /// Read the status of the magnet, e.g. to help positioning it during assembly.
+    /// ### Return
+    /// *Result<MagnetStatus, EncoderError>*
+    /// - `MagnetStatus`: detected, too weak, too strong or missing
+    /// - `EncoderError::Bus`: the I2C transaction with the sensor failed
+    pub fn try_magnet_status(
+        &mut self,
+    ) -> Result<MagnetStatus, EncoderError<I2cError<I2C::Error>>> {
+        let mut status = [0];
+        self.i2c
+            .write_read(AS5600_ADDRESS, &[AS5600_STATUS], &mut status)
+            .map_err(|e| EncoderError::Bus(I2cError(e)))?;
+        Ok(magnet_status(status[0]))
+    }
+}
+
+/// Magnet status from the STATUS register
+fn magnet_status(status: u8) -> MagnetStatus {
+    if status & STATUS_MAGNET_DETECTED == 0 {
+        MagnetStatus::Missing
+    } else if status & STATUS_MAGNET_TOO_WEAK != 0 {
+        MagnetStatus::TooWeak
+    } else if status & STATUS_MAGNET_TOO_STRONG != 0 {
+        MagnetStatus::TooStrong
+    } else {
+        MagnetStatus::Detected
+    }
+}
+
+impl<I2C> AbsoluteEncode for As5600<I2C>
+where
+    I2C: I2c,
+{
+    #[inline]
+    fn resolution(&self) -> u32 {
+        AS5600_RESOLUTION
+    }
+
+    /// ## Description
+    /// Read the angle of the magnet, in 1/4096 of a turn.
+    /// ### Return
+    /// *Result<u16, EncoderError>*
+    /// - `u16`: position, from 0 to 4095
+    /// - `EncoderError::Bus`: the I2C transaction with the sensor failed
+    /// - `EncoderError::NoMagnet`: no magnet is detected
+    fn try_position(&mut self) -> Result<u16, EncoderError<Self::PinError>> {
+        // STATUS, RAW ANGLE and ANGLE in a single transaction
+        let mut registers = [0; 5];
+        self.i2c
+            .write_read(AS5600_ADDRESS, &[AS5600_STATUS], &mut registers)
+            .map_err(|e| EncoderError::Bus(I2cError(e)))?;
+        if magnet_status(registers[0]) == MagnetStatus::Missing {
+            return Err(EncoderError::NoMagnet);
+        }
+        Ok(u16::from_be_bytes([registers[3], registers[4]]) & 0x0FFF)
+    }
+}
+
+impl<I2C> Encode for As5600<I2C>
+where
+    I2C: I2c,
+{
+    type PinError = I2cError<I2C::Error>;
+
+    /// ## Description
+    /// Read the angle and report the movement since the last reading.
+    /// ## Return
+    /// *Result<Direction, EncoderError>*
+    /// - `Direction`: CounterClockwise, Clockwise or Rest (the first reading is always at rest)
+    /// - `EncoderError`: the sensor could not be read, or no magnet is detected
+    #[inline]
+    fn try_encode(&mut self) -> Result<Direction, EncoderError<Self::PinError>> {
+        let position = self
+            .try_position()
+            .inspect_err(|_| self.read_errors = self.read_errors.saturating_add(1))?;
+        Ok(self.steps.update(position as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockedAs5600;
+
+    #[inline(never)]
+    #[test]
+    fn test_as5600_angle() {
+        let mut sensor = As5600::new(MockedAs5600::new(1024));
+
+        assert_eq!(Ok(MagnetStatus::Detected), sensor.try_magnet_status());
+        assert_eq!(Ok(1024), sensor.try_position());
+        assert_eq!(Ok(90_000), sensor.try_angle_mdeg());
+
+        // Magnet too far, then removed
+        sensor.i2c.status = STATUS_MAGNET_DETECTED | STATUS_MAGNET_TOO_WEAK;
+        assert_eq!(Ok(MagnetStatus::TooWeak), sensor.try_magnet_status());
+        assert_eq!(Ok(1024), sensor.try_position());
+        sensor.i2c.status = 0;
+        assert_eq!(Ok(MagnetStatus::Missing), sensor.try_magnet_status());
+        assert_eq!(Err(EncoderError::NoMagnet), sensor.try_position());
+
+        sensor.i2c.fault = true;
+        assert_eq!(
+            Err(EncoderError::Bus(I2cError(i2c::ErrorKind::Bus))),
+            sensor.try_position()
+        );
+        let error = I2cError(i2c::ErrorKind::NoAcknowledge(
+            i2c::NoAcknowledgeSource::Address,
+        ));
+        assert_eq!(
+            i2c::ErrorKind::NoAcknowledge(i2c::NoAcknowledgeSource::Address),
+            error.bus_kind()
+        );
+        assert_eq!(ErrorKind::Other, digital::Error::kind(&error));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_as5600_directions() {
+        let mut sensor = As5600::new(MockedAs5600::new(4000)).with_step_size(100);
+        let mut turn = |angle| {
+            sensor.i2c.angle = angle;
+            sensor.encode()
+        };
+
+        assert_eq!(Direction::Rest, turn(4000));
+        // Noise below a step is not reported
+        assert_eq!(Direction::Rest, turn(4060));
+        assert_eq!(Direction::Rest, turn(3990));
+        // Across the 0 position
+        assert_eq!(Direction::Clockwise, turn(10));
+        assert_eq!(Direction::Rest, turn(20));
+        assert_eq!(Direction::CounterClockwise, turn(3900));
+        assert_eq!(Direction::CounterClockwise, turn(3900));
+        assert_eq!(Direction::Rest, turn(3900));
+
+        // Failed readings are counted
+        sensor.i2c.status = 0;
+        assert_eq!(Direction::Rest, sensor.encode());
+        sensor.i2c.fault = true;
+        assert_eq!(Direction::Rest, sensor.encode());
+        assert_eq!(2, sensor.read_errors());
+    }
+}
@@ -0,0 +1,157 @@
+use core::future::poll_fn;
+use core::pin::pin;
+use core::task::Poll;
+
+use embedded_hal::digital::InputPin;
+use embedded_hal_async::digital::Wait;
+
+use crate::encoder::{Direction, Encode, EncoderError, QuadratureEncoder};
+
+/// ## Description
+/// A quadrature encoder which can be awaited instead of polled.
+///
+/// While the knob is untouched, the task sleeps until one of the pins changes level
+/// (`embedded_hal_async::digital::Wait`), so no time is spent reading an encoder at rest.
+/// Every change of level is then fed to the state machine of the encoder, which keeps its
+/// configuration (`StepMode`, polarity, glitch filter...).
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct AsyncEncoder<INPUT>
+where
+    INPUT: InputPin + Wait,
+{
+    encoder: QuadratureEncoder<INPUT>,
+}
+
+impl<INPUT> AsyncEncoder<INPUT>
+where
+    INPUT: InputPin + Wait,
+{
+    /// ## Description
+    /// Create an awaitable encoder.
+    /// ### Parameters
+    /// - encoder: an encoder whose pins implement `embedded_hal_async::digital::Wait`
+    /// ### Return
+    /// - AsyncEncoder
+    pub fn new(encoder: QuadratureEncoder<INPUT>) -> Self {
+        AsyncEncoder { encoder }
+    }
+
+    /// ## Description
+    /// Access the awaited encoder (e.g. for its diagnostics).
+    #[inline]
+    pub fn encoder(&self) -> &QuadratureEncoder<INPUT> {
+        &self.encoder
+    }
+
+    /// ## Description
+    /// Give back the encoder.
+    pub fn into_inner(self) -> QuadratureEncoder<INPUT> {
+        self.encoder
+    }
+
+    /// ## Description
+    /// Wait until the encoder moved by one step of its `StepMode`.
+    /// Invalid transitions are counted by the encoder and do not end the wait.
+    /// ### Return
+    /// *Result<Direction, EncoderError>*
+    /// - `Direction`: Clockwise or CounterClockwise
+    /// - `EncoderError::ReadPinState`: an error occured when reading or waiting for one of the pins
+    pub async fn wait_for_step(&mut self) -> Result<Direction, EncoderError<INPUT::Error>> {
+        loop {
+            match self.encoder.try_encode()? {
+                Direction::Rest | Direction::Invalid => self
+                    .wait_for_edge()
+                    .await
+                    .map_err(EncoderError::ReadPinState)?,
+                step => return Ok(step),
+            }
+        }
+    }
+
+    /// ## Description
+    /// Wait until one of the pins leaves the level of the last reading.
+    /// The awaited levels are absolute, so an edge happening before the wait starts is not lost.
+    async fn wait_for_edge(&mut self) -> Result<(), INPUT::Error> {
+        let (a_high, b_high) = self.encoder.pin_levels();
+        let mut a = pin!(wait_for_level(&mut self.encoder.a, !a_high));
+        let mut b = pin!(wait_for_level(&mut self.encoder.b, !b_high));
+        poll_fn(|cx| match a.as_mut().poll(cx) {
+            Poll::Pending => b.as_mut().poll(cx),
+            ready => ready,
+        })
+        .await
+    }
+}
+
+/// Wait until the pin reaches the given level.
+async fn wait_for_level<PIN: Wait>(pin: &mut PIN, high: bool) -> Result<(), PIN::Error> {
+    if high {
+        pin.wait_for_high().await
+    } else {
+        pin.wait_for_low().await
+    }
+}
+
+impl<INPUT> Encode for AsyncEncoder<INPUT>
+where
+    INPUT: InputPin + Wait,
+{
+    type PinError = INPUT::Error;
+
+    /// ## Description
+    /// (Forwards the `Encode` implementation of the Encoder)
+    #[inline]
+    fn try_encode(&mut self) -> Result<Direction, EncoderError<Self::PinError>> {
+        self.encoder.try_encode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::Hy040;
+    use crate::test_utils::{MockedGpioPin, block_on};
+    use embedded_hal::digital::{ErrorKind, PinState};
+
+    #[inline(never)]
+    #[test]
+    fn test_async_encoder_wait_for_step() {
+        let clk = MockedGpioPin {
+            state: PinState::High,
+            fault: false,
+        };
+        let dt = MockedGpioPin {
+            state: PinState::High,
+            fault: false,
+        };
+        let mut encoder = AsyncEncoder::new(Hy040::new(clk, dt));
+
+        // At rest: the mocked CLK pin reaches the awaited level, 11 -> 01
+        assert_eq!(Ok(Direction::Clockwise), block_on(encoder.wait_for_step()));
+        assert_eq!(PinState::Low, encoder.encoder.a.state);
+
+        // An edge which happened before waiting is not lost: 01 -> 00
+        encoder.encoder.b.state = PinState::Low;
+        assert_eq!(Ok(Direction::Clockwise), block_on(encoder.wait_for_step()));
+        assert_eq!(PinState::Low, encoder.encoder.a.state);
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_async_encoder_faulty() {
+        let clk = MockedGpioPin {
+            state: PinState::High,
+            fault: true,
+        };
+        let mut encoder = AsyncEncoder::new(Hy040::new(clk, MockedGpioPin::default()));
+
+        assert_eq!(
+            Err(EncoderError::ReadPinState(ErrorKind::Other)),
+            block_on(encoder.wait_for_step())
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::encoder::Direction;
+
+/// ## Description
+/// Behaviour of a `Position` reaching one of its bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    #[default]
+    Clamp, // The position stays on the bound
+    Wrap, // The position continues from the other bound
+}
+
+/// ## Description
+/// Position accumulated from the directions reported by an encoder, bounded by `min` and `max` (included).
+///
+/// ## Example
+///
+/// ```rust
+///     use hl_driver::encoder::{Direction, Overflow, Position};
+///     let mut position = Position::new(0, 9).with_overflow(Overflow::Wrap);
+///     assert_eq!(9, position.update(Direction::CounterClockwise));
+///     assert_eq!(0, position.update(Direction::Clockwise));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    value: i32,
+    initial: i32,
+    min: i32,
+    max: i32,
+    overflow: Overflow,
+}
+
+impl Position {
+    /// ## Description
+    /// Create a clamped position starting at `min`.
+    /// ### Parameters
+    /// - min: lowest position
+    /// - max: highest position
+    /// ### Panics
+    /// If `min` is greater than `max`.
+    /// ### Return
+    /// - Position
+    pub fn new(min: i32, max: i32) -> Self {
+        assert!(
+            min <= max,
+            "The minimum position must not exceed the maximum"
+        );
+        Position {
+            value: min,
+            initial: min,
+            min,
+            max,
+            overflow: Overflow::default(),
+        }
+    }
+
+    /// ## Description
+    /// Set the behaviour of the position when reaching one of its bounds.
+    /// ### Parameters
+    /// - overflow: clamp or wrap
+    /// ### Return
+    /// Position with the given overflow behaviour
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// ## Description
+    /// Set the position at creation and after a reset.
+    /// ### Parameters
+    /// - initial: initial position, brought within the bounds
+    /// ### Return
+    /// Position starting at the given value
+    pub fn with_initial(mut self, initial: i32) -> Self {
+        self.set(initial);
+        self.initial = self.value;
+        self
+    }
+
+    /// ## Description
+    /// Current position.
+    #[inline]
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    /// ## Description
+    /// Move the position back to its initial value.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.value = self.initial;
+    }
+
+    /// ## Description
+    /// Set the position, the value is clamped or wrapped within the bounds.
+    /// ### Parameters
+    /// - value: new position
+    pub fn set(&mut self, value: i32) {
+        self.place(value as i64);
+    }
+
+    /// ## Description
+    /// Move the position by several steps at once.
+    /// ### Parameters
+    /// - steps: number of steps, positive clockwise
+    /// ### Return
+    /// - `i32`: the new position
+    pub fn step(&mut self, steps: i32) -> i32 {
+        self.place(self.value as i64 + steps as i64);
+        self.value
+    }
+
+    /// ## Description
+    /// Move the position by one step in the given direction.
+    /// ### Parameters
+    /// - direction: direction reported by an encoder, `Clockwise` increases the position
+    ///   (`Invalid` directions are ignored)
+    /// ### Return
+    /// - `i32`: the new position
+    #[inline]
+    pub fn update(&mut self, direction: Direction) -> i32 {
+        match direction {
+            Direction::Clockwise => self.step(1),
+            Direction::CounterClockwise => self.step(-1),
+            Direction::Rest | Direction::Invalid => self.value,
+        }
+    }
+
+    /// Bring a value within the bounds (computed on 64 bits so it cannot overflow).
+    fn place(&mut self, value: i64) {
+        let (min, max) = (self.min as i64, self.max as i64);
+        self.value = match self.overflow {
+            Overflow::Clamp => value.clamp(min, max),
+            Overflow::Wrap => min + (value - min).rem_euclid(max - min + 1),
+        } as i32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[inline(never)]
+    #[test]
+    fn test_position_clamp() {
+        let mut position = Position::new(0, 120).with_initial(118);
+
+        assert_eq!(119, position.update(Direction::Clockwise));
+        assert_eq!(120, position.update(Direction::Clockwise));
+        assert_eq!(120, position.update(Direction::Clockwise));
+        assert_eq!(120, position.update(Direction::Rest));
+        assert_eq!(0, position.step(-500));
+
+        position.reset();
+        assert_eq!(118, position.value());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_position_wrap() {
+        let mut position = Position::new(1, 90).with_overflow(Overflow::Wrap);
+
+        assert_eq!(90, position.update(Direction::CounterClockwise));
+        assert_eq!(1, position.update(Direction::Clockwise));
+        assert_eq!(11, position.step(190));
+
+        // Extreme bounds do not overflow
+        let mut position = Position::new(i32::MIN, i32::MAX).with_overflow(Overflow::Wrap);
+        position.set(i32::MAX);
+        assert_eq!(i32::MIN, position.update(Direction::Clockwise));
+    }
+}
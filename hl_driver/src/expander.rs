@@ -0,0 +1,661 @@
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use embedded_hal::digital::{self, ErrorKind, ErrorType, InputPin, OutputPin};
+use embedded_hal::i2c::{self, I2c};
+
+// Default addresses, with the A2..A0 address pins tied low
+const PCF8574_DEFAULT_ADDRESS: u8 = 0x20;
+const MCP23017_DEFAULT_ADDRESS: u8 = 0x20;
+
+// MCP23017 registers (IOCON.BANK = 0, A and B registers are interleaved)
+const MCP23017_IODIR: u8 = 0x00;
+const MCP23017_GPINTEN: u8 = 0x04;
+const MCP23017_IOCON: u8 = 0x0A;
+const MCP23017_GPPU: u8 = 0x0C;
+const MCP23017_GPIO: u8 = 0x12;
+const MCP23017_OLAT: u8 = 0x14;
+// INTA and INTB mirrored, open drain interrupt output
+const MCP23017_IOCON_MIRROR_ODR: u8 = 0x44;
+
+/*************************************/
+/*************************************/
+/******** TRAITS AND ENUMS ***********/
+/*************************************/
+/*************************************/
+
+/// ## Description
+///
+/// Trait defining how a port expander chip is accessed over I2C.
+/// The pins of the port are seen as a bitfield, pin `n` being bit `n`.
+pub trait Chip {
+    /// Number of pins of the port
+    const PINS: u8;
+
+    /// Put the chip in a known state: all pins are inputs, interrupts are configured.
+    fn init<I2C: I2c>(&self, i2c: &mut I2C) -> Result<(), I2C::Error>;
+
+    /// Configure the direction of the pins and the level of the outputs.
+    fn configure<I2C: I2c>(
+        &self,
+        i2c: &mut I2C,
+        inputs: u16,
+        outputs: u16,
+    ) -> Result<(), I2C::Error>;
+
+    /// Update the level of the outputs.
+    fn write<I2C: I2c>(&self, i2c: &mut I2C, inputs: u16, outputs: u16) -> Result<(), I2C::Error>;
+
+    /// Read the level of all the pins of the port (this clears the interrupt of the chip).
+    fn read<I2C: I2c>(&self, i2c: &mut I2C) -> Result<u16, I2C::Error>;
+}
+
+/// ## Description
+///
+/// Possible errors related to port expanders
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExpanderError<E> {
+    Bus(E),       // The I2C transaction with the chip failed
+    InvalidPin,   // The chip has no such pin
+    InterruptPin, // The interrupt pin could not be read
+}
+
+/// ## Description
+///
+/// Marker of a pin configured as an input (weak pull-up enabled).
+#[derive(Debug)]
+pub struct Input;
+
+/// ## Description
+///
+/// Marker of a pin configured as an output.
+#[derive(Debug)]
+pub struct Output;
+
+/*************************************/
+/*************************************/
+/* EMBEDDED HAL TRAIT IMPLEMENTATION */
+/*************************************/
+/*************************************/
+
+impl<E> digital::Error for ExpanderError<E>
+where
+    E: i2c::Error,
+{
+    /// ## Description
+    ///
+    /// `embedded_hal::digital::ErrorKind` has no other kind than `Other`, so every error maps to it.
+    /// The kind of a failed I2C transaction is given by `ExpanderError::bus_kind`.
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl<E> ExpanderError<E>
+where
+    E: i2c::Error,
+{
+    /// ## Description
+    ///
+    /// Kind of the I2C error behind a failed transaction with the chip.
+    ///
+    /// ## Return
+    /// - `Option<i2c::ErrorKind>`: kind of the bus error, `None` if the error does not come from the bus
+    pub fn bus_kind(&self) -> Option<i2c::ErrorKind> {
+        match self {
+            ExpanderError::Bus(error) => Some(error.kind()),
+            ExpanderError::InvalidPin | ExpanderError::InterruptPin => None,
+        }
+    }
+}
+
+impl<I2C, CHIP, MODE> ErrorType for ExpanderPin<'_, I2C, CHIP, MODE>
+where
+    I2C: I2c,
+    CHIP: Chip,
+{
+    type Error = ExpanderError<I2C::Error>;
+}
+
+/*************************************/
+/*************************************/
+/*************** CHIPS ***************/
+/*************************************/
+/*************************************/
+
+/// ## Description
+///
+/// PCF8574 8 bits quasi-bidirectional port expander.
+///
+/// The pins have no direction register: an input is an output latched high, pulled up by the
+/// weak internal current source. The INT output is asserted (low) when an input changes and is
+/// released when the port is read.
+#[derive(Debug)]
+pub struct Pcf8574 {
+    address: u8,
+}
+
+impl Pcf8574 {
+    /// ## Description
+    ///
+    /// ## Parameters
+    /// - `address`: 7 bits I2C address of the chip (0x20..=0x27, or 0x38..=0x3F for the PCF8574A)
+    ///
+    /// ## Return
+    /// - Pcf8574
+    pub fn new(address: u8) -> Self {
+        Pcf8574 { address }
+    }
+}
+
+impl Default for Pcf8574 {
+    fn default() -> Self {
+        Pcf8574::new(PCF8574_DEFAULT_ADDRESS)
+    }
+}
+
+impl Chip for Pcf8574 {
+    const PINS: u8 = 8;
+
+    fn init<I2C: I2c>(&self, i2c: &mut I2C) -> Result<(), I2C::Error> {
+        i2c.write(self.address, &[0xFF])
+    }
+
+    fn configure<I2C: I2c>(
+        &self,
+        i2c: &mut I2C,
+        inputs: u16,
+        outputs: u16,
+    ) -> Result<(), I2C::Error> {
+        self.write(i2c, inputs, outputs)
+    }
+
+    fn write<I2C: I2c>(&self, i2c: &mut I2C, inputs: u16, outputs: u16) -> Result<(), I2C::Error> {
+        // Inputs have to stay latched high to be read
+        i2c.write(self.address, &[(outputs | inputs) as u8])
+    }
+
+    fn read<I2C: I2c>(&self, i2c: &mut I2C) -> Result<u16, I2C::Error> {
+        let mut port = [0];
+        i2c.read(self.address, &mut port)?;
+        Ok(port[0] as u16)
+    }
+}
+
+/// ## Description
+///
+/// MCP23017 16 bits port expander (GPA0..7 are pins 0 to 7, GPB0..7 are pins 8 to 15).
+///
+/// Inputs use the internal pull-ups and raise an interrupt on change. INTA and INTB are mirrored
+/// and configured as open drain outputs, so either of them can be wired (active low).
+#[derive(Debug)]
+pub struct Mcp23017 {
+    address: u8,
+}
+
+impl Mcp23017 {
+    /// ## Description
+    ///
+    /// ## Parameters
+    /// - `address`: 7 bits I2C address of the chip (0x20..=0x27)
+    ///
+    /// ## Return
+    /// - Mcp23017
+    pub fn new(address: u8) -> Self {
+        Mcp23017 { address }
+    }
+
+    /// Write a pair of A/B registers.
+    fn write_register<I2C: I2c>(
+        &self,
+        i2c: &mut I2C,
+        register: u8,
+        value: u16,
+    ) -> Result<(), I2C::Error> {
+        let [a, b] = value.to_le_bytes();
+        i2c.write(self.address, &[register, a, b])
+    }
+}
+
+impl Default for Mcp23017 {
+    fn default() -> Self {
+        Mcp23017::new(MCP23017_DEFAULT_ADDRESS)
+    }
+}
+
+impl Chip for Mcp23017 {
+    const PINS: u8 = 16;
+
+    fn init<I2C: I2c>(&self, i2c: &mut I2C) -> Result<(), I2C::Error> {
+        i2c.write(self.address, &[MCP23017_IOCON, MCP23017_IOCON_MIRROR_ODR])?;
+        self.configure(i2c, 0xFFFF, 0)
+    }
+
+    fn configure<I2C: I2c>(
+        &self,
+        i2c: &mut I2C,
+        inputs: u16,
+        outputs: u16,
+    ) -> Result<(), I2C::Error> {
+        // Latch the outputs before changing the direction to avoid glitches
+        self.write_register(i2c, MCP23017_OLAT, outputs)?;
+        self.write_register(i2c, MCP23017_IODIR, inputs)?;
+        self.write_register(i2c, MCP23017_GPPU, inputs)?;
+        self.write_register(i2c, MCP23017_GPINTEN, inputs)
+    }
+
+    fn write<I2C: I2c>(&self, i2c: &mut I2C, _inputs: u16, outputs: u16) -> Result<(), I2C::Error> {
+        self.write_register(i2c, MCP23017_OLAT, outputs)
+    }
+
+    fn read<I2C: I2c>(&self, i2c: &mut I2C) -> Result<u16, I2C::Error> {
+        let mut port = [0; 2];
+        i2c.write_read(self.address, &[MCP23017_GPIO], &mut port)?;
+        Ok(u16::from_le_bytes(port))
+    }
+}
+
+/*************************************/
+/*************************************/
+/************* EXPANDER **************/
+/*************************************/
+/*************************************/
+
+/// ## Description
+///
+/// State of the port shared by the pins of the expander.
+#[derive(Debug)]
+struct Port<I2C> {
+    i2c: I2C,
+    inputs: u16,
+    outputs: u16,
+    last_read: u16,
+}
+
+/// ## Description
+///
+/// I2C port expander whose pins implement `embedded_hal::digital::InputPin` and `OutputPin`,
+/// so they can be used by switches, encoders and keypads like any gpio pin.
+///
+/// The pins borrow the expander: every access is a transaction on the I2C bus owned by the
+/// expander. Inputs are read live, `service_interrupt` and `poll_interrupt` report which inputs
+/// changed since the last read of the port (by any pin) when the INT output of the chip is wired.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct PortExpander<I2C, CHIP>
+where
+    I2C: I2c,
+    CHIP: Chip,
+{
+    chip: CHIP,
+    port: RefCell<Port<I2C>>,
+}
+
+impl<I2C, CHIP> PortExpander<I2C, CHIP>
+where
+    I2C: I2c,
+    CHIP: Chip,
+{
+    /// ## Description
+    ///
+    /// Create a new port expander and initialize the chip: all the pins are inputs.
+    ///
+    /// ## Parameters
+    /// - `i2c`: the I2C bus of the chip, implementing `embedded_hal::i2c::I2c`
+    /// - `chip`: the chip (`Pcf8574` or `Mcp23017`) and its address
+    ///
+    /// ## Return
+    /// *Result<PortExpander, ExpanderError>*
+    /// - `ExpanderError::Bus`: the chip could not be initialized
+    pub fn new(mut i2c: I2C, chip: CHIP) -> Result<Self, ExpanderError<I2C::Error>> {
+        chip.init(&mut i2c).map_err(ExpanderError::Bus)?;
+        let all = u16::MAX >> (16 - CHIP::PINS);
+        Ok(PortExpander {
+            chip,
+            port: RefCell::new(Port {
+                i2c,
+                inputs: all,
+                outputs: 0,
+                last_read: all,
+            }),
+        })
+    }
+
+    /// ## Description
+    ///
+    /// Give back the I2C bus used by the expander.
+    pub fn into_inner(self) -> I2C {
+        self.port.into_inner().i2c
+    }
+
+    /// ## Description
+    ///
+    /// Configure a pin of the expander as an input, with its weak pull-up.
+    ///
+    /// ## Parameters
+    /// - `index`: index of the pin, from 0 to `CHIP::PINS - 1`
+    ///
+    /// ## Return
+    /// *Result<ExpanderPin, ExpanderError>*
+    /// - `ExpanderError::InvalidPin`: the chip has no such pin
+    /// - `ExpanderError::Bus`: the pin could not be configured
+    pub fn input(
+        &self,
+        index: u8,
+    ) -> Result<ExpanderPin<'_, I2C, CHIP, Input>, ExpanderError<I2C::Error>> {
+        let bit = Self::bit(index)?;
+        self.configure(|port| port.inputs |= bit)?;
+        Ok(ExpanderPin {
+            expander: self,
+            bit,
+            mode: PhantomData,
+        })
+    }
+
+    /// ## Description
+    ///
+    /// Configure a pin of the expander as an output.
+    ///
+    /// ## Parameters
+    /// - `index`: index of the pin, from 0 to `CHIP::PINS - 1`
+    /// - `initial`: level of the output once configured
+    ///
+    /// ## Return
+    /// *Result<ExpanderPin, ExpanderError>*
+    /// - `ExpanderError::InvalidPin`: the chip has no such pin
+    /// - `ExpanderError::Bus`: the pin could not be configured
+    pub fn output(
+        &self,
+        index: u8,
+        initial: digital::PinState,
+    ) -> Result<ExpanderPin<'_, I2C, CHIP, Output>, ExpanderError<I2C::Error>> {
+        let bit = Self::bit(index)?;
+        self.configure(|port| {
+            port.inputs &= !bit;
+            match initial {
+                digital::PinState::High => port.outputs |= bit,
+                digital::PinState::Low => port.outputs &= !bit,
+            }
+        })?;
+        Ok(ExpanderPin {
+            expander: self,
+            bit,
+            mode: PhantomData,
+        })
+    }
+
+    /// ## Description
+    ///
+    /// Read the port after the INT output of the chip has been asserted (e.g. from the handler of
+    /// the falling edge of the gpio wired to INT). Reading the port clears the interrupt.
+    ///
+    /// ## Return
+    /// *Result<u16, ExpanderError>*
+    /// - `u16`: inputs which changed since the last read of the port, as a bitfield
+    /// - `ExpanderError::Bus`: the port could not be read
+    pub fn service_interrupt(&self) -> Result<u16, ExpanderError<I2C::Error>> {
+        let mut port = self.port.borrow_mut();
+        let previous = port.last_read;
+        let current = self.read(&mut port)?;
+        Ok((previous ^ current) & port.inputs)
+    }
+
+    /// ## Description
+    ///
+    /// Check the INT output of the chip (active low) and service the interrupt if it is asserted.
+    /// The bus is only accessed when an interrupt is pending.
+    ///
+    /// ## Parameters
+    /// - `int`: the gpio pin wired to the INT output of the chip, implementing `embedded_hal::digital::InputPin`
+    ///
+    /// ## Return
+    /// *Result<u16, ExpanderError>*
+    /// - `u16`: inputs which changed since the last read of the port, as a bitfield (0 if no interrupt)
+    /// - `ExpanderError::InterruptPin`: the interrupt pin could not be read
+    /// - `ExpanderError::Bus`: the port could not be read
+    pub fn poll_interrupt<INT>(&self, int: &mut INT) -> Result<u16, ExpanderError<I2C::Error>>
+    where
+        INT: InputPin,
+    {
+        match int.is_low() {
+            Ok(true) => self.service_interrupt(),
+            Ok(false) => Ok(0),
+            Err(_) => Err(ExpanderError::InterruptPin),
+        }
+    }
+
+    /// Bit of a pin in the port bitfield.
+    fn bit(index: u8) -> Result<u16, ExpanderError<I2C::Error>> {
+        if index < CHIP::PINS {
+            Ok(1 << index)
+        } else {
+            Err(ExpanderError::InvalidPin)
+        }
+    }
+
+    /// Apply a change of configuration to the port and send it to the chip.
+    fn configure<F>(&self, change: F) -> Result<(), ExpanderError<I2C::Error>>
+    where
+        F: FnOnce(&mut Port<I2C>),
+    {
+        let mut port = self.port.borrow_mut();
+        change(&mut port);
+        let Port {
+            i2c,
+            inputs,
+            outputs,
+            ..
+        } = &mut *port;
+        self.chip
+            .configure(i2c, *inputs, *outputs)
+            .map_err(ExpanderError::Bus)
+    }
+
+    /// Read the port and keep track of the last read value.
+    fn read(&self, port: &mut Port<I2C>) -> Result<u16, ExpanderError<I2C::Error>> {
+        let value = self.chip.read(&mut port.i2c).map_err(ExpanderError::Bus)?;
+        port.last_read = value;
+        Ok(value)
+    }
+
+    /// Set the level of some outputs.
+    fn write(&self, bit: u16, high: bool) -> Result<(), ExpanderError<I2C::Error>> {
+        let mut port = self.port.borrow_mut();
+        match high {
+            true => port.outputs |= bit,
+            false => port.outputs &= !bit,
+        }
+        let Port {
+            i2c,
+            inputs,
+            outputs,
+            ..
+        } = &mut *port;
+        self.chip
+            .write(i2c, *inputs, *outputs)
+            .map_err(ExpanderError::Bus)
+    }
+}
+
+/*************************************/
+/*************************************/
+/*************** PINS ****************/
+/*************************************/
+/*************************************/
+
+/// ## Description
+///
+/// Single pin of a `PortExpander`, configured as an `Input` or an `Output`.
+/// Implements `embedded_hal::digital::InputPin` (inputs) or `OutputPin` (outputs).
+///
+/// Several handles on the same pin are not prevented, the last configuration applies.
+#[derive(Debug)]
+pub struct ExpanderPin<'a, I2C, CHIP, MODE>
+where
+    I2C: I2c,
+    CHIP: Chip,
+{
+    expander: &'a PortExpander<I2C, CHIP>,
+    bit: u16,
+    mode: PhantomData<MODE>,
+}
+
+impl<I2C, CHIP> InputPin for ExpanderPin<'_, I2C, CHIP, Input>
+where
+    I2C: I2c,
+    CHIP: Chip,
+{
+    #[inline]
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        let mut port = self.expander.port.borrow_mut();
+        Ok(self.expander.read(&mut port)? & self.bit != 0)
+    }
+
+    #[inline]
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_high()?)
+    }
+}
+
+impl<I2C, CHIP> OutputPin for ExpanderPin<'_, I2C, CHIP, Output>
+where
+    I2C: I2c,
+    CHIP: Chip,
+{
+    #[inline]
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.expander.write(self.bit, false)
+    }
+
+    #[inline]
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.expander.write(self.bit, true)
+    }
+}
+
+/*************************************/
+/*************************************/
+/************** TESTS ****************/
+/*************************************/
+/*************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::switch::{Pressable, Switch, SwitchError, SwitchState};
+    use crate::test_utils::{MockedGpioPin, MockedMcp23017, MockedPcf8574};
+    use embedded_hal::digital::PinState;
+
+    #[inline(never)]
+    #[test]
+    fn test_pcf8574_pins() {
+        let expander = PortExpander::new(MockedPcf8574::new(0x21), Pcf8574::new(0x21))
+            .expect("Problem when initializing the expander");
+        let mut input = expander
+            .input(0)
+            .expect("Problem when configuring the input");
+        let mut output = expander
+            .output(1, PinState::Low)
+            .expect("Problem when configuring the output");
+
+        // Inputs stay latched high, outputs follow their level
+        assert_eq!(0xFD, expander.port.borrow().i2c.latch);
+        output.set_high().unwrap();
+        assert_eq!(0xFF, expander.port.borrow().i2c.latch);
+
+        // Inputs follow the external level
+        assert!(input.is_high().unwrap());
+        expander.port.borrow_mut().i2c.set_levels(0xFE);
+        assert!(input.is_low().unwrap());
+
+        // Pins beyond the port do not exist
+        assert!(matches!(expander.input(8), Err(ExpanderError::InvalidPin)));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_mcp23017_configuration() {
+        let expander = PortExpander::new(MockedMcp23017::new(0x20), Mcp23017::default())
+            .expect("Problem when initializing the expander");
+        let _input = expander
+            .input(9)
+            .expect("Problem when configuring the input");
+        let mut output = expander
+            .output(3, PinState::High)
+            .expect("Problem when configuring the output");
+        output.set_low().unwrap();
+
+        let port = expander.port.borrow();
+        // Interrupts are mirrored and open drain
+        assert_eq!(0x44, port.i2c.registers[MCP23017_IOCON as usize]);
+        // Only pin 3 is an output, inputs are pulled up and raise interrupts
+        assert_eq!(0xFFF7, port.i2c.register(MCP23017_IODIR as usize));
+        assert_eq!(0xFFF7, port.i2c.register(MCP23017_GPPU as usize));
+        assert_eq!(0xFFF7, port.i2c.register(MCP23017_GPINTEN as usize));
+        assert_eq!(0x0000, port.i2c.register(MCP23017_OLAT as usize));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_expander_interrupt() {
+        let expander = PortExpander::new(MockedMcp23017::new(0x20), Mcp23017::default())
+            .expect("Problem when initializing the expander");
+        let _output = expander.output(0, PinState::Low).unwrap();
+        let mut int = MockedGpioPin {
+            state: PinState::High,
+            fault: false,
+        };
+
+        // No interrupt, no change
+        assert_eq!(Ok(0), expander.poll_interrupt(&mut int));
+
+        // Pins 4 and 12 are pulled low, the chip asserts INT
+        expander.port.borrow_mut().i2c.set_levels(!0x1010);
+        assert!(expander.port.borrow().i2c.interrupt);
+        int.state = PinState::Low;
+        assert_eq!(Ok(0x1010), expander.poll_interrupt(&mut int));
+        // Reading the port cleared the interrupt
+        assert!(!expander.port.borrow().i2c.interrupt);
+        assert_eq!(Ok(0), expander.service_interrupt());
+
+        // The interrupt pin cannot be read
+        int.fault = true;
+        let error = expander.poll_interrupt(&mut int).unwrap_err();
+        assert_eq!(ExpanderError::InterruptPin, error);
+        assert_eq!(None, error.bus_kind());
+
+        // The kind of a bus error is kept
+        int.fault = false;
+        expander.port.borrow_mut().i2c.fault = true;
+        let error = expander.poll_interrupt(&mut int).unwrap_err();
+        assert_eq!(Some(i2c::ErrorKind::Bus), error.bus_kind());
+        assert_eq!(ErrorKind::Other, digital::Error::kind(&error));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_switch_on_expander() {
+        let expander = PortExpander::new(MockedPcf8574::new(0x20), Pcf8574::default())
+            .expect("Problem when initializing the expander");
+        let pin = expander
+            .input(5)
+            .expect("Problem when configuring the input");
+        let mut switch = Switch::new(pin, PinState::Low);
+
+        assert_eq!(Ok(false), switch.has_been_pressed());
+        expander.port.borrow_mut().i2c.set_levels(!0x20);
+        assert_eq!(Ok(true), switch.has_been_pressed());
+
+        // Bus errors are carried up to the switch
+        expander.port.borrow_mut().i2c.fault = true;
+        assert_eq!(
+            Err(SwitchError::ReadPinState(ExpanderError::Bus(
+                i2c::ErrorKind::Bus
+            ))),
+            switch.try_get_current_state()
+        );
+        assert_eq!(SwitchState::Faulty, switch.get_current_state());
+    }
+}
@@ -0,0 +1,560 @@
+use core::cell::Cell;
+use core::convert::Infallible;
+use embedded_hal::digital::{Error, ErrorKind, InputPin, OutputPin};
+
+use crate::debounce::{Debounce, DebounceState};
+use crate::switch::{Pressable, SwitchError, SwitchState};
+
+// Keys states are stored as bits in a u64
+const MAX_KEYS: usize = 64;
+
+/*************************************/
+/*************************************/
+/******** TRAITS AND ENUMS ***********/
+/*************************************/
+/*************************************/
+
+/// ## Description
+///
+/// Possible errors related to keypads.
+/// `RE` and `CE` are the errors of the row and column pins, so the root cause of a failure is not lost.
+#[derive(Debug, PartialEq)]
+pub enum KeypadError<RE, CE> {
+    DriveRow(RE),   // An error occured when driving a row pin
+    ReadColumn(CE), // An error occured when reading a column pin
+}
+
+impl<RE, CE> Error for KeypadError<RE, CE>
+where
+    RE: Error,
+    CE: Error,
+{
+    fn kind(&self) -> ErrorKind {
+        match self {
+            KeypadError::DriveRow(e) => e.kind(), // Fwd the pin error kind
+            KeypadError::ReadColumn(e) => e.kind(),
+        }
+    }
+}
+
+/// ## Description
+///
+/// Kind of change of a key
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KeyEventKind {
+    Pressed,
+    Released,
+}
+
+/// ## Description
+///
+/// Debounced change of a key of the matrix
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct KeyEvent {
+    pub row: u8,
+    pub col: u8,
+    pub kind: KeyEventKind,
+}
+
+/*************************************/
+/*************************************/
+/************** KEYPAD ***************/
+/*************************************/
+/*************************************/
+
+/// ## Description
+///
+/// Matrix keypad of `R` rows and `C` columns (up to 64 keys).
+///
+/// The rows are outputs driven low one at a time, the columns are inputs pulled up:
+/// a key is pressed when its column reads low while its row is driven.
+/// Every key is debounced individually with its own debouncer.
+///
+/// Without diodes, pressing three keys at the corners of a rectangle makes the fourth one appear
+/// pressed (ghosting). When such a pattern is detected, the scan is discarded and the keys keep
+/// their previous state until the pattern disappears.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct Keypad<ROW, COL, D, const R: usize, const C: usize>
+where
+    ROW: OutputPin,
+    COL: InputPin,
+    D: Debounce,
+{
+    rows: [ROW; R],
+    cols: [COL; C],
+    debouncers: [[D; C]; R],
+    pressed: u64,
+    ghosting: bool,
+    pending_press: Cell<u64>,
+    pending_release: Cell<u64>,
+}
+
+impl<ROW, COL, D, const R: usize, const C: usize> Keypad<ROW, COL, D, R, C>
+where
+    ROW: OutputPin,
+    COL: InputPin,
+    D: Debounce + Default,
+{
+    /// ## Description
+    ///
+    /// Create a new keypad from its row and column pins, with one default debouncer per key.
+    ///
+    /// ## Parameters
+    /// - `rows`: the gpio pins connected to the rows, implementing `embedded_hal::digital::OutputPin`
+    /// - `cols`: the gpio pins connected to the columns (pulled up), implementing `embedded_hal::digital::InputPin`
+    ///
+    /// ## Panics
+    /// If the keypad has more than 64 keys.
+    ///
+    /// ## Return
+    /// - Keypad
+    pub fn new(rows: [ROW; R], cols: [COL; C]) -> Self {
+        assert!(R * C <= MAX_KEYS, "A keypad supports up to 64 keys");
+        Keypad {
+            rows,
+            cols,
+            debouncers: core::array::from_fn(|_| core::array::from_fn(|_| D::default())),
+            pressed: 0,
+            ghosting: false,
+            pending_press: Cell::new(0),
+            pending_release: Cell::new(0),
+        }
+    }
+}
+
+impl<ROW, COL, D, const R: usize, const C: usize> Keypad<ROW, COL, D, R, C>
+where
+    ROW: OutputPin,
+    COL: InputPin,
+    D: Debounce,
+{
+    /// ## Description
+    ///
+    /// Scan the whole matrix once and debounce every key.
+    /// Has to be called periodically (from the main loop or a timer).
+    ///
+    /// ## Return
+    /// *Result<KeyEvents, KeypadError>*
+    /// - `KeyEvents`: iterator over the keys pressed or released during this scan
+    /// - `KeypadError`: an error occured when driving a row or reading a column
+    pub fn scan(&mut self) -> Result<KeyEvents, KeypadError<ROW::Error, COL::Error>> {
+        let raw = self.read_matrix()?;
+
+        self.ghosting = Self::has_ghosting(raw);
+        if self.ghosting {
+            return Ok(KeyEvents::empty(C));
+        }
+
+        let previous = self.pressed;
+        for (row, debouncers) in self.debouncers.iter_mut().enumerate() {
+            for (col, debouncer) in debouncers.iter_mut().enumerate() {
+                let bit = Self::bit(row, col);
+                debouncer.debounce(raw & bit != 0);
+                match debouncer.get_state() {
+                    DebounceState::Loaded => self.pressed |= bit,
+                    DebounceState::Unloaded => self.pressed &= !bit,
+                    // Keep the last known level while debouncing
+                    DebounceState::Transition => (),
+                }
+            }
+        }
+
+        let pressed = self.pressed & !previous;
+        let released = previous & !self.pressed;
+        self.pending_press.set(self.pending_press.get() | pressed);
+        self.pending_release
+            .set(self.pending_release.get() | released);
+
+        Ok(KeyEvents {
+            pressed,
+            released,
+            cols: C,
+        })
+    }
+
+    /// ## Description
+    ///
+    /// Indicate if the last scan has been discarded because of ghosting.
+    #[inline]
+    pub fn is_ghosting(&self) -> bool {
+        self.ghosting
+    }
+
+    /// ## Description
+    ///
+    /// Debounced state of a key after the last scan.
+    ///
+    /// ## Parameters
+    /// - `row`: row of the key
+    /// - `col`: column of the key
+    ///
+    /// ## Panics
+    /// If the keypad has no such key.
+    ///
+    /// ## Return
+    /// - `bool`: `true` if the key is pressed
+    #[inline]
+    pub fn is_pressed(&self, row: u8, col: u8) -> bool {
+        self.pressed & Self::bit(row as usize, col as usize) != 0
+    }
+
+    /// ## Description
+    ///
+    /// Borrow a single key of the keypad as a switch implementing `hl_driver::switch::Pressable`.
+    /// The key reads the result of the last scan, it does not sample anything itself.
+    ///
+    /// ## Parameters
+    /// - `row`: row of the key
+    /// - `col`: column of the key
+    ///
+    /// ## Panics
+    /// If the keypad has no such key.
+    ///
+    /// ## Return
+    /// - KeyHandle
+    pub fn key(&self, row: u8, col: u8) -> KeyHandle<'_> {
+        KeyHandle {
+            bit: Self::bit(row as usize, col as usize),
+            pressed: self.pressed,
+            ghosting: self.ghosting,
+            pending_press: &self.pending_press,
+            pending_release: &self.pending_release,
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Drive every row in turn and read the columns.
+    /// All the rows are released (high) afterwards, even if a column could not be read.
+    fn read_matrix(&mut self) -> Result<u64, KeypadError<ROW::Error, COL::Error>> {
+        let mut raw = 0;
+        for (row, row_pin) in self.rows.iter_mut().enumerate() {
+            row_pin.set_low().map_err(KeypadError::DriveRow)?;
+            let cols = Self::read_columns(&mut self.cols);
+            // Release the row before reporting a column error, or it would be read with the next rows
+            row_pin.set_high().map_err(KeypadError::DriveRow)?;
+            raw |= cols.map_err(KeypadError::ReadColumn)? << (row * C);
+        }
+        Ok(raw)
+    }
+
+    /// ## Description
+    ///
+    /// Read the columns of the driven row, bit `n` for column `n`.
+    fn read_columns(cols: &mut [COL; C]) -> Result<u64, COL::Error> {
+        let mut pressed = 0;
+        for (col, col_pin) in cols.iter_mut().enumerate() {
+            if col_pin.is_low()? {
+                pressed |= 1 << col;
+            }
+        }
+        Ok(pressed)
+    }
+
+    /// ## Description
+    ///
+    /// Ghosting appears as two rows sharing at least two pressed columns (rectangle pattern).
+    fn has_ghosting(raw: u64) -> bool {
+        // Built by shifting down so a 64 columns keypad does not overflow
+        let row_mask = u64::MAX.checked_shr((MAX_KEYS - C) as u32).unwrap_or(0);
+        (0..R).any(|first| {
+            let first_cols = (raw >> (first * C)) & row_mask;
+            (first + 1..R).any(|second| {
+                let second_cols = (raw >> (second * C)) & row_mask;
+                (first_cols & second_cols).count_ones() >= 2
+            })
+        })
+    }
+
+    #[inline]
+    fn bit(row: usize, col: usize) -> u64 {
+        assert!(row < R && col < C, "The keypad has no such key");
+        1 << (row * C + col)
+    }
+}
+
+/// ## Description
+///
+/// Iterator over the key events of a scan.
+#[derive(Debug, Clone)]
+pub struct KeyEvents {
+    pressed: u64,
+    released: u64,
+    cols: usize,
+}
+
+impl KeyEvents {
+    fn empty(cols: usize) -> Self {
+        KeyEvents {
+            pressed: 0,
+            released: 0,
+            cols,
+        }
+    }
+
+    fn event(&self, bit: u32, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent {
+            row: (bit as usize / self.cols) as u8,
+            col: (bit as usize % self.cols) as u8,
+            kind,
+        }
+    }
+}
+
+impl Iterator for KeyEvents {
+    type Item = KeyEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pressed != 0 {
+            let bit = self.pressed.trailing_zeros();
+            self.pressed &= !(1 << bit);
+            Some(self.event(bit, KeyEventKind::Pressed))
+        } else if self.released != 0 {
+            let bit = self.released.trailing_zeros();
+            self.released &= !(1 << bit);
+            Some(self.event(bit, KeyEventKind::Released))
+        } else {
+            None
+        }
+    }
+}
+
+/// ## Description
+///
+/// Single key of a `Keypad` seen as a switch.
+/// Implements the Pressable trait.
+#[derive(Debug)]
+pub struct KeyHandle<'a> {
+    bit: u64,
+    pressed: u64,
+    ghosting: bool,
+    pending_press: &'a Cell<u64>,
+    pending_release: &'a Cell<u64>,
+}
+
+impl Pressable for KeyHandle<'_> {
+    // Pin errors are reported by `Keypad::scan`, a handle never fails
+    type PinError = Infallible;
+
+    /// ## Description
+    ///
+    /// Return the debounced state of the key after the last scan.
+    ///
+    /// ## Return
+    /// *Result<SwitchState, SwitchError>*
+    /// - `SwitchState`: Pressed, Released or Transition (the last scan has been discarded because of ghosting)
+    #[inline]
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
+        Ok(if self.ghosting {
+            SwitchState::Transition
+        } else if self.pressed & self.bit != 0 {
+            SwitchState::Pressed
+        } else {
+            SwitchState::Released
+        })
+    }
+
+    /// ## Description
+    ///
+    /// Return if the key has been pressed since the last use of this method
+    /// (on any handle borrowed for the same key).
+    #[inline]
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        let pending = self.pending_press.get();
+        self.pending_press.set(pending & !self.bit);
+        Ok(pending & self.bit != 0)
+    }
+
+    /// ## Description
+    ///
+    /// Return if the key has been released since the last use of this method
+    /// (on any handle borrowed for the same key).
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        let pending = self.pending_release.get();
+        self.pending_release.set(pending & !self.bit);
+        Ok(pending & self.bit != 0)
+    }
+
+    /// ## Description
+    ///
+    /// The keypad does not measure time, press durations are not available.
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        None
+    }
+}
+
+/*************************************/
+/*************************************/
+/************** TESTS ****************/
+/*************************************/
+/*************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debounce::LatchingDebouncer;
+    use crate::test_utils::{MockedColumnPin, MockedMatrix, MockedRowPin};
+
+    type TestKeypad<'a> = Keypad<MockedRowPin<'a>, MockedColumnPin<'a>, LatchingDebouncer, 4, 3>;
+
+    fn keypad(matrix: &MockedMatrix) -> TestKeypad<'_> {
+        Keypad::new(
+            core::array::from_fn(|row| matrix.row(row)),
+            core::array::from_fn(|col| matrix.col(col)),
+        )
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_keypad_scan_events() {
+        let matrix = MockedMatrix::new(3);
+        let mut keypad = keypad(&matrix);
+
+        // Press the key on row 2, column 1
+        matrix.press(2, 1);
+        for _ in 0..2 {
+            assert_eq!(None, keypad.scan().expect("Problem when scanning").next());
+        }
+        let mut events = keypad.scan().expect("Problem when scanning");
+        assert_eq!(
+            Some(KeyEvent {
+                row: 2,
+                col: 1,
+                kind: KeyEventKind::Pressed
+            }),
+            events.next()
+        );
+        assert_eq!(None, events.next());
+        assert!(keypad.is_pressed(2, 1));
+
+        // Release it
+        matrix.release(2, 1);
+        for _ in 0..7 {
+            assert_eq!(None, keypad.scan().expect("Problem when scanning").next());
+        }
+        let mut events = keypad.scan().expect("Problem when scanning");
+        assert_eq!(
+            Some(KeyEvent {
+                row: 2,
+                col: 1,
+                kind: KeyEventKind::Released
+            }),
+            events.next()
+        );
+        assert_eq!(None, events.next());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_keypad_key_handles() {
+        let matrix = MockedMatrix::new(3);
+        let mut keypad = keypad(&matrix);
+
+        matrix.press(0, 0);
+        matrix.press(3, 2);
+        for _ in 0..3 {
+            keypad.scan().expect("Problem when scanning");
+        }
+
+        let mut key = keypad.key(3, 2);
+        assert_eq!(SwitchState::Pressed, key.get_current_state());
+        assert!(key.has_been_pressed().unwrap());
+        assert!(!key.has_been_pressed().unwrap());
+        assert!(keypad.key(0, 0).has_been_pressed().unwrap());
+        assert_eq!(SwitchState::Released, keypad.key(1, 1).get_current_state());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_keypad_ghosting() {
+        let matrix = MockedMatrix::new(3);
+        let mut keypad = keypad(&matrix);
+
+        // Two keys on the same row
+        matrix.press(0, 0);
+        matrix.press(0, 1);
+        for _ in 0..3 {
+            keypad.scan().expect("Problem when scanning");
+        }
+        assert!(!keypad.is_ghosting());
+
+        // A third key completing a rectangle makes (1, 1) appear pressed
+        matrix.press(1, 0);
+        for _ in 0..3 {
+            assert_eq!(None, keypad.scan().expect("Problem when scanning").next());
+        }
+        assert!(keypad.is_ghosting());
+        assert!(!keypad.is_pressed(1, 0));
+        assert!(!keypad.is_pressed(1, 1));
+        assert_eq!(
+            SwitchState::Transition,
+            keypad.key(1, 0).get_current_state()
+        );
+
+        // Releasing one of the keys removes the ghost
+        matrix.release(0, 1);
+        keypad.scan().expect("Problem when scanning");
+        assert!(!keypad.is_ghosting());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_keypad_column_error() {
+        let matrix = MockedMatrix::new(3);
+        let mut keypad = keypad(&matrix);
+
+        // The error of the pin is reported and the row is released
+        matrix.set_column_fault(true);
+        assert_eq!(
+            Err(KeypadError::ReadColumn(
+                embedded_hal::digital::ErrorKind::Other
+            )),
+            keypad.scan().map(|_| ())
+        );
+        assert_eq!(0, matrix.driven_rows());
+
+        // The next scans only read one row at a time
+        matrix.set_column_fault(false);
+        matrix.press(0, 0);
+        matrix.press(1, 1);
+        for _ in 0..3 {
+            keypad.scan().expect("Problem when scanning");
+        }
+        assert!(keypad.is_pressed(0, 0));
+        assert!(!keypad.is_pressed(1, 0));
+        assert!(!keypad.is_ghosting());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_keypad_ghosting_64_columns() {
+        type WideKeypad<'a> =
+            Keypad<MockedRowPin<'a>, MockedColumnPin<'a>, LatchingDebouncer, 1, 64>;
+
+        // A single row cannot ghost, the row mask covers the 64 columns
+        assert!(!WideKeypad::has_ghosting(u64::MAX));
+    }
+
+    #[inline(never)]
+    #[test]
+    #[should_panic(expected = "The keypad has no such key")]
+    fn test_keypad_is_pressed_out_of_range() {
+        let matrix = MockedMatrix::new(3);
+        // Column 3 would otherwise read the key on row 1, column 0
+        keypad(&matrix).is_pressed(0, 3);
+    }
+
+    #[inline(never)]
+    #[test]
+    #[should_panic(expected = "The keypad has no such key")]
+    fn test_keypad_key_out_of_range() {
+        let matrix = MockedMatrix::new(3);
+        keypad(&matrix).key(4, 0);
+    }
+}
@@ -0,0 +1,406 @@
+use core::cell::Cell;
+use core::convert::Infallible;
+
+use crate::adc::AnalogInput;
+use crate::debounce::{BankEdges, Debounce, DebounceState};
+use crate::switch::{Pressable, SwitchError, SwitchState};
+
+// Buttons states are stored as bits in a u32
+const MAX_BUTTONS: usize = 32;
+
+/*************************************/
+/*************************************/
+/******** TRAITS AND ENUMS ***********/
+/*************************************/
+/*************************************/
+
+/// ## Description
+///
+/// Range of voltages (in millivolts, bounds included) read on the analog input while a button
+/// of the ladder is pressed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct VoltageWindow {
+    pub min_mv: u16,
+    pub max_mv: u16,
+}
+
+impl VoltageWindow {
+    /// ## Description
+    ///
+    /// ## Parameters
+    /// - `min_mv`: lowest voltage of the window
+    /// - `max_mv`: highest voltage of the window
+    ///
+    /// ## Return
+    /// - VoltageWindow
+    pub fn new(min_mv: u16, max_mv: u16) -> Self {
+        VoltageWindow { min_mv, max_mv }
+    }
+
+    /// ## Description
+    ///
+    /// Window centered on the nominal voltage of a button, as computed from the resistor values.
+    ///
+    /// ## Parameters
+    /// - `nominal_mv`: voltage expected while the button is pressed
+    /// - `tolerance_mv`: accepted deviation on both sides (resistor tolerance, ADC noise)
+    ///
+    /// ## Return
+    /// - VoltageWindow
+    pub fn around(nominal_mv: u16, tolerance_mv: u16) -> Self {
+        VoltageWindow {
+            min_mv: nominal_mv.saturating_sub(tolerance_mv),
+            max_mv: nominal_mv.saturating_add(tolerance_mv),
+        }
+    }
+
+    /// ## Description
+    ///
+    /// ## Return
+    /// - `bool`: `true` if the voltage is within the window
+    #[inline]
+    pub fn contains(&self, mv: u16) -> bool {
+        self.min_mv <= mv && mv <= self.max_mv
+    }
+
+    fn overlaps(&self, other: &VoltageWindow) -> bool {
+        self.min_mv <= other.max_mv && other.min_mv <= self.max_mv
+    }
+}
+
+/*************************************/
+/*************************************/
+/************** LADDER ***************/
+/*************************************/
+/*************************************/
+
+/// ## Description
+///
+/// `N` buttons (up to 32) wired through a resistor ladder to a single analog input.
+///
+/// Each button pulls the input to its own voltage, a voltage outside of every window means that
+/// no button is pressed. Only one button can be detected at a time: pressing several buttons
+/// produces the voltage of the button closest to the input, or a voltage outside of the windows.
+/// Every button is debounced individually with its own debouncer.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct ResistorLadder<ADC, D, const N: usize>
+where
+    ADC: AnalogInput,
+    D: Debounce,
+{
+    adc: ADC,
+    windows: [VoltageWindow; N],
+    debouncers: [D; N],
+    pressed: u32,
+    transition: u32,
+    pending_press: Cell<u32>,
+    pending_release: Cell<u32>,
+}
+
+impl<ADC, D, const N: usize> ResistorLadder<ADC, D, N>
+where
+    ADC: AnalogInput,
+    D: Debounce + Default,
+{
+    /// ## Description
+    ///
+    /// Create a new resistor ladder with one default debouncer per button.
+    ///
+    /// ## Parameters
+    /// - `adc`: the analog input of the ladder, implementing `hl_driver::adc::AnalogInput`
+    /// - `windows`: the voltage window of each button
+    ///
+    /// ## Panics
+    /// If the ladder has more than 32 buttons, or if a window is empty or overlaps another one.
+    ///
+    /// ## Return
+    /// - ResistorLadder
+    pub fn new(adc: ADC, windows: [VoltageWindow; N]) -> Self {
+        assert!(
+            N <= MAX_BUTTONS,
+            "A resistor ladder supports up to 32 buttons"
+        );
+        for (index, window) in windows.iter().enumerate() {
+            assert!(
+                window.min_mv <= window.max_mv,
+                "Voltage windows must not be empty"
+            );
+            assert!(
+                !windows[index + 1..]
+                    .iter()
+                    .any(|other| window.overlaps(other)),
+                "Voltage windows must not overlap"
+            );
+        }
+        ResistorLadder {
+            adc,
+            windows,
+            debouncers: core::array::from_fn(|_| D::default()),
+            pressed: 0,
+            transition: 0,
+            pending_press: Cell::new(0),
+            pending_release: Cell::new(0),
+        }
+    }
+}
+
+impl<ADC, D, const N: usize> ResistorLadder<ADC, D, N>
+where
+    ADC: AnalogInput,
+    D: Debounce,
+{
+    /// ## Description
+    ///
+    /// Sample the analog input once and debounce every button.
+    /// Has to be called periodically (from the main loop or a timer).
+    ///
+    /// ## Return
+    /// *Result<BankEdges<u32>, ADC::Error>*
+    /// - `BankEdges`: masks of the buttons which have just been pressed or released (bit `n` for button `n`)
+    /// - `ADC::Error`: the analog input could not be read, the buttons keep their state
+    pub fn scan(&mut self) -> Result<BankEdges<u32>, ADC::Error> {
+        let mv = self.adc.read_mv()?;
+        let active = self.windows.iter().position(|window| window.contains(mv));
+
+        let previous = self.pressed;
+        self.transition = 0;
+        for (index, debouncer) in self.debouncers.iter_mut().enumerate() {
+            let bit = 1 << index;
+            debouncer.debounce(active == Some(index));
+            match debouncer.get_state() {
+                DebounceState::Loaded => self.pressed |= bit,
+                DebounceState::Unloaded => self.pressed &= !bit,
+                // Keep the last known level while debouncing
+                DebounceState::Transition => self.transition |= bit,
+            }
+        }
+
+        let edges = BankEdges {
+            pressed: self.pressed & !previous,
+            released: previous & !self.pressed,
+        };
+        self.pending_press
+            .set(self.pending_press.get() | edges.pressed);
+        self.pending_release
+            .set(self.pending_release.get() | edges.released);
+        Ok(edges)
+    }
+
+    /// ## Description
+    ///
+    /// Debounced state of a button after the last scan.
+    ///
+    /// ## Parameters
+    /// - `index`: index of the button, in the order of the windows
+    ///
+    /// ## Panics
+    /// If the ladder has no such button.
+    ///
+    /// ## Return
+    /// - `bool`: `true` if the button is pressed
+    #[inline]
+    pub fn is_pressed(&self, index: u8) -> bool {
+        assert!((index as usize) < N, "The ladder has no such button");
+        self.pressed & 1 << index != 0
+    }
+
+    /// ## Description
+    ///
+    /// Borrow a single button of the ladder as a switch implementing `hl_driver::switch::Pressable`.
+    /// The button reads the result of the last scan, it does not sample anything itself.
+    ///
+    /// ## Parameters
+    /// - `index`: index of the button, in the order of the windows
+    ///
+    /// ## Panics
+    /// If the ladder has no such button.
+    ///
+    /// ## Return
+    /// - LadderButton
+    pub fn button(&self, index: u8) -> LadderButton<'_> {
+        assert!((index as usize) < N, "The ladder has no such button");
+        let bit = 1 << index;
+        let state = if self.transition & bit != 0 {
+            SwitchState::Transition
+        } else if self.pressed & bit != 0 {
+            SwitchState::Pressed
+        } else {
+            SwitchState::Released
+        };
+        LadderButton {
+            bit,
+            state,
+            pending_press: &self.pending_press,
+            pending_release: &self.pending_release,
+        }
+    }
+}
+
+/// ## Description
+///
+/// Single button of a `ResistorLadder` seen as a switch.
+/// Implements the Pressable trait.
+#[derive(Debug)]
+pub struct LadderButton<'a> {
+    bit: u32,
+    state: SwitchState,
+    pending_press: &'a Cell<u32>,
+    pending_release: &'a Cell<u32>,
+}
+
+impl Pressable for LadderButton<'_> {
+    // ADC errors are reported by `ResistorLadder::scan`, a button never fails
+    type PinError = Infallible;
+
+    /// ## Description
+    ///
+    /// Return the debounced state of the button after the last scan.
+    ///
+    /// ## Return
+    /// *Result<SwitchState, SwitchError>*
+    /// - `SwitchState`: Pressed, Released or Transition (debouncing is ongoing)
+    #[inline]
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
+        Ok(self.state)
+    }
+
+    /// ## Description
+    ///
+    /// Return if the button has been pressed since the last use of this method
+    /// (on any button borrowed for the same index).
+    #[inline]
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        let pending = self.pending_press.get();
+        self.pending_press.set(pending & !self.bit);
+        Ok(pending & self.bit != 0)
+    }
+
+    /// ## Description
+    ///
+    /// Return if the button has been released since the last use of this method
+    /// (on any button borrowed for the same index).
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        let pending = self.pending_release.get();
+        self.pending_release.set(pending & !self.bit);
+        Ok(pending & self.bit != 0)
+    }
+
+    /// ## Description
+    ///
+    /// The ladder does not measure time, press durations are not available.
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        None
+    }
+}
+
+/*************************************/
+/*************************************/
+/************** TESTS ****************/
+/*************************************/
+/*************************************/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debounce::{Debouncer, LatchingDebouncer};
+    use crate::test_utils::MockedAdc;
+
+    // 3 buttons on a 3.3 V ladder, the input idles at 3300 mV
+    fn windows() -> [VoltageWindow; 3] {
+        [
+            VoltageWindow::around(0, 150),
+            VoltageWindow::around(1100, 150),
+            VoltageWindow::around(2200, 150),
+        ]
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_ladder_scan_edges() {
+        let mut adc = MockedAdc::new(3300);
+        let mut ladder: ResistorLadder<_, LatchingDebouncer, 3> =
+            ResistorLadder::new(&mut adc, windows());
+
+        // Nothing pressed
+        assert_eq!(
+            BankEdges {
+                pressed: 0,
+                released: 0
+            },
+            ladder.scan().unwrap()
+        );
+
+        // Button 1 pressed, with some noise on the voltage
+        ladder.adc.millivolts = 1180;
+        for _ in 0..2 {
+            assert_eq!(0, ladder.scan().unwrap().pressed);
+        }
+        ladder.adc.millivolts = 1040;
+        assert_eq!(0b010, ladder.scan().unwrap().pressed);
+        assert!(ladder.is_pressed(1));
+        assert!(!ladder.is_pressed(0));
+
+        // Released: the latching debouncer needs 8 samples
+        ladder.adc.millivolts = 3300;
+        for _ in 0..7 {
+            assert_eq!(0, ladder.scan().unwrap().released);
+        }
+        assert_eq!(0b010, ladder.scan().unwrap().released);
+
+        // Reading errors are reported and keep the state
+        ladder.adc.fault = true;
+        assert!(ladder.scan().is_err());
+        assert!(!ladder.is_pressed(1));
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_ladder_buttons() {
+        let mut ladder: ResistorLadder<_, Debouncer, 3> =
+            ResistorLadder::new(MockedAdc::new(3300), windows());
+
+        ladder.adc.millivolts = 2250;
+        ladder.scan().unwrap();
+        // The shift register debouncer needs 3 samples
+        assert_eq!(
+            SwitchState::Transition,
+            ladder.button(2).get_current_state()
+        );
+        assert_eq!(SwitchState::Released, ladder.button(0).get_current_state());
+        ladder.scan().unwrap();
+        ladder.scan().unwrap();
+
+        let mut button = ladder.button(2);
+        assert_eq!(SwitchState::Pressed, button.get_current_state());
+        assert!(button.has_been_pressed().unwrap());
+        assert!(!button.has_been_pressed().unwrap());
+        assert!(!ladder.button(1).has_been_pressed().unwrap());
+    }
+
+    #[inline(never)]
+    #[test]
+    #[should_panic(expected = "The ladder has no such button")]
+    fn test_ladder_is_pressed_out_of_range() {
+        let ladder: ResistorLadder<_, Debouncer, 2> = ResistorLadder::new(
+            MockedAdc::new(3300),
+            [VoltageWindow::new(0, 1000), VoltageWindow::new(1100, 2000)],
+        );
+        ladder.is_pressed(2);
+    }
+
+    #[inline(never)]
+    #[test]
+    #[should_panic]
+    fn test_ladder_overlapping_windows() {
+        let _: ResistorLadder<_, Debouncer, 2> = ResistorLadder::new(
+            MockedAdc::new(3300),
+            [VoltageWindow::new(0, 1000), VoltageWindow::new(900, 2000)],
+        );
+    }
+}
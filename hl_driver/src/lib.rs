@@ -1,8 +1,13 @@
 #![cfg_attr(not(feature = "unit-tests"), no_std)]
 
+pub mod adc;
 pub mod debounce;
 pub mod encoder;
+pub mod expander;
+pub mod keypad;
+pub mod ladder;
 pub mod switch;
+pub mod time;
 
 #[cfg(any(test, doc))]
 pub mod test_utils;
@@ -1,6 +1,21 @@
 use embedded_hal::digital::{Error, ErrorKind, ErrorType, InputPin, PinState};
 
-use crate::debounce::{self, DebounceState};
+use crate::debounce::{self, DebounceError, DebounceState};
+use crate::time::{Monotonic, NoClock};
+
+mod async_switch;
+mod bank;
+mod chord;
+mod gesture;
+mod supervisor;
+mod toggle;
+
+pub use async_switch::AsyncSwitch;
+pub use bank::BankSwitch;
+pub use chord::{ChordDetector, ChordEvent, ChordTiming};
+pub use gesture::{ButtonEvent, ClickDetector, GestureTiming};
+pub use supervisor::{SupervisedSwitch, SupervisorConfig};
+pub use toggle::{ToggleEvent, ToggleSwitch};
 
 /*************************************/
 /*************************************/
@@ -12,8 +27,65 @@ use crate::debounce::{self, DebounceState};
 ///
 /// Trait defining common switch behaviour
 pub trait Pressable {
-    fn get_current_state(&mut self) -> SwitchState;
-    fn has_been_pressed(&mut self) -> Result<bool, SwitchError>;
+    /// Error of the underlying input, carried by `SwitchError::ReadPinState`
+    type PinError: Error;
+
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>>;
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>>;
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>>;
+
+    /// ## Description
+    ///
+    /// Return how long the switch was held during its last complete press, in milliseconds.
+    /// Switches which do not measure their presses always return `None`.
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        None
+    }
+
+    /// ## Description
+    ///
+    /// Infallible version of `try_get_current_state`: any error is reported as `SwitchState::Faulty`.
+    #[inline]
+    fn get_current_state(&mut self) -> SwitchState {
+        self.try_get_current_state().unwrap_or(SwitchState::Faulty)
+    }
+}
+
+/// ## Description
+///
+/// Allow a switch to be used through a mutable reference, for instance to combine switches
+/// of different types as `&mut dyn Pressable<PinError = E>`.
+impl<P> Pressable for &mut P
+where
+    P: Pressable + ?Sized,
+{
+    type PinError = P::PinError;
+
+    #[inline]
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
+        (**self).try_get_current_state()
+    }
+
+    #[inline]
+    fn get_current_state(&mut self) -> SwitchState {
+        (**self).get_current_state()
+    }
+
+    #[inline]
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        (**self).has_been_pressed()
+    }
+
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        (**self).has_been_released()
+    }
+
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        (**self).last_press_duration()
+    }
 }
 
 /// ## Description
@@ -40,10 +112,24 @@ impl From<SwitchState> for bool {
 
 /// ## Description
 ///
-/// Possible errors related to switches
-#[derive(Debug, PartialEq)]
-pub enum SwitchError {
-    ReadPinState,
+/// Possible errors related to switches.
+/// `E` is the error of the underlying input (e.g. `InputPin::Error`), so the root cause of a read
+/// failure (gpio, port expander bus, ...) is not lost.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SwitchError<E> {
+    ReadPinState(E),         // The gpio pin of the switch could not be read
+    Stuck,                   // The switch has been held for an implausible duration
+    Chattering,              // The switch changes state implausibly fast
+    Debounce(DebounceError), // The debouncer of the switch is misconfigured
+}
+
+/// ## Description
+///
+/// Allow debouncer configuration errors to be propagated as switch errors.
+impl<E> From<DebounceError> for SwitchError<E> {
+    fn from(value: DebounceError) -> Self {
+        SwitchError::Debounce(value)
+    }
 }
 
 /*************************************/
@@ -52,19 +138,26 @@ pub enum SwitchError {
 /*************************************/
 /*************************************/
 
-impl Error for SwitchError {
+impl<E> Error for SwitchError<E>
+where
+    E: Error,
+{
     fn kind(&self) -> ErrorKind {
         match self {
-            SwitchError::ReadPinState => ErrorKind::Other,
+            SwitchError::ReadPinState(e) => e.kind(), // Fwd the pin error kind
+            SwitchError::Stuck => ErrorKind::Other,
+            SwitchError::Chattering => ErrorKind::Other,
+            SwitchError::Debounce(_) => ErrorKind::Other,
         }
     }
 }
 
-impl<PIN> ErrorType for Switch<PIN>
+impl<PIN, C> ErrorType for Switch<PIN, C>
 where
     PIN: InputPin,
+    C: Monotonic,
 {
-    type Error = SwitchError;
+    type Error = SwitchError<PIN::Error>;
 }
 
 /*************************************/
@@ -80,18 +173,26 @@ where
 /// A simple switch with no specific capabilities.
 /// Implements the Pressable trait.
 ///
+/// Press durations are only measured when a clock has been attached with `with_clock`.
+///
 /// ## Example
 ///
 /// See unit tests for example of use.
 ///
 #[derive(Debug, PartialEq)]
-pub struct Switch<PIN>
+pub struct Switch<PIN, C = NoClock>
 where
     PIN: InputPin,
+    C: Monotonic,
 {
     pin: PIN,
     pressed_state: PinState,
     last_state: SwitchState,
+    pending_press: bool,
+    pending_release: bool,
+    clock: C,
+    pressed_at: u64,
+    last_press_duration: Option<u64>,
 }
 
 /********* IMPLEMENTATION *************/
@@ -116,9 +217,45 @@ where
             pin,
             pressed_state,
             last_state: SwitchState::Released,
+            pending_press: false,
+            pending_release: false,
+            clock: NoClock,
+            pressed_at: 0,
+            last_press_duration: None,
         }
     }
 
+    /// ## Description
+    ///
+    /// Attach a clock to the switch to measure the duration of the presses.
+    ///
+    /// ## Parameters
+    /// - `clock`: a clock implementing `hl_driver::time::Monotonic`
+    ///
+    /// ## Return
+    /// - Switch measuring its press durations
+    pub fn with_clock<C>(self, clock: C) -> Switch<PIN, C>
+    where
+        C: Monotonic,
+    {
+        Switch {
+            pin: self.pin,
+            pressed_state: self.pressed_state,
+            last_state: self.last_state,
+            pending_press: self.pending_press,
+            pending_release: self.pending_release,
+            clock,
+            pressed_at: 0,
+            last_press_duration: None,
+        }
+    }
+}
+
+impl<PIN, C> Switch<PIN, C>
+where
+    PIN: InputPin,
+    C: Monotonic,
+{
     /// ## Description
     ///
     /// Add a debouncer to a simple switch. The functions of the switch are filtered through the debouncer.
@@ -128,43 +265,83 @@ where
     ///
     /// ## Return
     /// - DebouncedSwitch
-    pub fn with_debounce<D>(self, debouncer: D) -> DebouncedSwitch<PIN, D>
+    pub fn with_debounce<D>(self, debouncer: D) -> DebouncedSwitch<PIN, D, C>
     where
         D: debounce::Debounce,
     {
         DebouncedSwitch {
             switch: self,
             debouncer,
+            diagnostics: None,
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Keep track of the pressed and released edges based on a new state of the switch.
+    /// `Transition` states are ignored, the edges are computed between stable levels.
+    fn track_edges(&mut self, state: SwitchState) {
+        match state {
+            SwitchState::Transition | SwitchState::Faulty => return,
+            SwitchState::Pressed if self.last_state != SwitchState::Pressed => {
+                self.pending_press = true;
+                self.pressed_at = self.clock.now_ms();
+            }
+            SwitchState::Released if self.last_state == SwitchState::Pressed => {
+                self.pending_release = true;
+                if self.clock.is_running() {
+                    self.last_press_duration = Some(self.clock.now_ms() - self.pressed_at);
+                }
+            }
+            _ => (),
         }
+        self.last_state = state;
+    }
+
+    /// ## Description
+    ///
+    /// Consume the pending pressed edge, if any.
+    #[inline]
+    fn take_press(&mut self) -> bool {
+        core::mem::take(&mut self.pending_press)
+    }
+
+    /// ## Description
+    ///
+    /// Consume the pending released edge, if any.
+    #[inline]
+    fn take_release(&mut self) -> bool {
+        core::mem::take(&mut self.pending_release)
     }
 }
 
-impl<PIN> Pressable for Switch<PIN>
+impl<PIN, C> Pressable for Switch<PIN, C>
 where
     PIN: InputPin,
+    C: Monotonic,
 {
+    type PinError = PIN::Error;
+
     /// ## Description
     ///
     /// Return the state of the switch when the function is invoqued.
     ///
     /// ## Return
-    /// SwitchState:
-    /// - Pressed
-    /// - Released
-    /// - Faulty
-    ///
-    /// (The Transition state is not returned since there is no debouncing).
+    /// *Result<SwitchState, SwitchError>*
+    /// - `SwitchState`: Pressed or Released
+    ///   (the Transition state is not returned since there is no debouncing)
+    /// - `SwitchError::ReadPinState`: an error occured when reading the gpio pin of the switch
     #[inline]
-    fn get_current_state(&mut self) -> SwitchState {
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
         match self.pin.is_high() {
             Ok(b) => {
                 if b == bool::from(self.pressed_state) {
-                    SwitchState::Pressed
+                    Ok(SwitchState::Pressed)
                 } else {
-                    SwitchState::Released
+                    Ok(SwitchState::Released)
                 }
             }
-            Err(_) => SwitchState::Faulty,
+            Err(e) => Err(SwitchError::ReadPinState(e)),
         }
     }
 
@@ -177,17 +354,36 @@ where
     /// - `bool`: `true` if the switch has been pressed, `false` otherwise
     /// -  `SwitchError::ReadPinState`: an error occured when reading the gpio pin of the switch
     #[inline]
-    fn has_been_pressed(&mut self) -> Result<bool, SwitchError> {
-        let current_state = self.get_current_state();
-        match current_state {
-            SwitchState::Faulty => Err(SwitchError::ReadPinState),
-            _ => {
-                let was_pressed = self.last_state != SwitchState::Pressed
-                    && current_state == SwitchState::Pressed;
-                self.last_state = current_state;
-                Ok(was_pressed)
-            }
-        }
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        let current_state = self.try_get_current_state()?;
+        self.track_edges(current_state);
+        Ok(self.take_press())
+    }
+
+    /// ## Description
+    ///
+    /// Return if the switch has been released since the last use of this method.
+    ///
+    /// ## Return
+    /// *Result<bool, SwitchError>*
+    /// - `bool`: `true` if the switch has been released, `false` otherwise
+    /// -  `SwitchError::ReadPinState`: an error occured when reading the gpio pin of the switch
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        let current_state = self.try_get_current_state()?;
+        self.track_edges(current_state);
+        Ok(self.take_release())
+    }
+
+    /// ## Description
+    ///
+    /// Return how long the switch was held during its last complete press.
+    ///
+    /// ## Return
+    /// - `Option<u64>`: duration in milliseconds, `None` without clock or before the first release
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        self.last_press_duration
     }
 }
 
@@ -203,49 +399,83 @@ where
 /// See unit tests for example of use.
 ///
 #[derive(Debug, PartialEq)]
-pub struct DebouncedSwitch<PIN, D>
+pub struct DebouncedSwitch<PIN, D, C = NoClock>
 where
     PIN: InputPin,
     D: debounce::Debounce,
+    C: Monotonic,
 {
-    switch: Switch<PIN>,
+    switch: Switch<PIN, C>,
     debouncer: D,
+    diagnostics: Option<BounceDiagnostics>,
 }
 
 /********* IMPLEMENTATION *************/
 
-impl<PIN, D> Pressable for DebouncedSwitch<PIN, D>
+impl<PIN, D, C> DebouncedSwitch<PIN, D, C>
+where
+    PIN: InputPin,
+    D: debounce::Debounce,
+    C: Monotonic,
+{
+    /// ## Description
+    ///
+    /// Enable the recording of bounce statistics. See `BounceDiagnostics` for details.
+    ///
+    /// ## Return
+    /// - DebouncedSwitch recording its bounce statistics
+    pub fn with_diagnostics(mut self) -> Self {
+        self.diagnostics = Some(BounceDiagnostics::default());
+        self
+    }
+
+    /// ## Description
+    ///
+    /// Bounce statistics recorded since the diagnostics have been enabled.
+    ///
+    /// ## Return
+    /// - `Option<&BounceDiagnostics>`: `None` if the diagnostics are not enabled
+    #[inline]
+    pub fn diagnostics(&self) -> Option<&BounceDiagnostics> {
+        self.diagnostics.as_ref()
+    }
+}
+
+impl<PIN, D, C> Pressable for DebouncedSwitch<PIN, D, C>
 where
     PIN: InputPin,
     D: debounce::Debounce,
+    C: Monotonic,
 {
+    type PinError = PIN::Error;
+
     /// ## Description
     ///
     /// Return the state of the switch when the function is invoqued.
     ///
     /// ## Return
-    /// SwitchState:
-    /// - Pressed
-    /// - Released
-    /// - Transition (debouncing is ongoing)
-    /// - Faulty
+    /// *Result<SwitchState, SwitchError>*
+    /// - `SwitchState`: Pressed, Released or Transition (debouncing is ongoing)
+    /// - `SwitchError::ReadPinState`: an error occured when reading the gpio pin of the switch
     #[inline]
-    fn get_current_state(&mut self) -> SwitchState {
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
         match self.switch.pin.is_high() {
             Ok(b) => {
-                if b == bool::from(self.switch.pressed_state) {
-                    self.debouncer.debounce(true);
-                } else {
-                    self.debouncer.debounce(false);
-                }
+                let pressed = b == bool::from(self.switch.pressed_state);
+                self.debouncer.debounce(pressed);
 
-                match self.debouncer.get_state() {
+                let state = match self.debouncer.get_state() {
                     debounce::DebounceState::Loaded => SwitchState::Pressed,
                     DebounceState::Transition => SwitchState::Transition,
                     DebounceState::Unloaded => SwitchState::Released,
+                };
+
+                if let Some(diagnostics) = self.diagnostics.as_mut() {
+                    diagnostics.record(pressed, state);
                 }
+                Ok(state)
             }
-            Err(_) => SwitchState::Faulty,
+            Err(e) => Err(SwitchError::ReadPinState(e)),
         }
     }
 
@@ -260,18 +490,149 @@ where
     /// - `bool`: `true` if the switch has been pressed, `false` otherwise
     /// - `SwitchError::ReadPinState`: an error occured when reading the gpio pin of the switch
     #[inline]
-    fn has_been_pressed(&mut self) -> Result<bool, SwitchError> {
-        let current_state = self.get_current_state();
-        match current_state {
-            SwitchState::Faulty => Err(SwitchError::ReadPinState),
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        let current_state = self.try_get_current_state()?;
+        self.switch.track_edges(current_state);
+        Ok(self.switch.take_press())
+    }
+
+    /// ## Description
+    ///
+    /// Return if the switch has been released since the last use of this method.
+    ///
+    /// This takes into account the debouncing.
+    ///
+    /// ## Return
+    /// *Result<bool, SwitchError>*
+    /// - `bool`: `true` if the switch has been released, `false` otherwise
+    /// - `SwitchError::ReadPinState`: an error occured when reading the gpio pin of the switch
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        let current_state = self.try_get_current_state()?;
+        self.switch.track_edges(current_state);
+        Ok(self.switch.take_release())
+    }
+
+    /// ## Description
+    ///
+    /// Return how long the switch was held during its last complete press, between the debounced edges.
+    ///
+    /// ## Return
+    /// - `Option<u64>`: duration in milliseconds, `None` without clock or before the first release
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        self.switch.last_press_duration()
+    }
+}
+
+/********* DIAGNOSTICS *************/
+
+/// ## Description
+///
+/// Bounce statistics recorded by a `DebouncedSwitch`, useful to spot worn switches.
+///
+/// The statistics are based on the raw samples of the pin disagreeing with the debounced level, so they
+/// are recorded whether the debouncer reports `Transition` states or keeps the latched level:
+/// - a press is a `Released` -> `Pressed` change, its bounce length is the number of pressed samples
+///   seen before the press is confirmed
+/// - an aborted press is a run of pressed samples which ends with the pin back to released, without
+///   the press being confirmed (and without the debouncer still in `Transition`)
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct BounceDiagnostics {
+    presses: u32,
+    aborted_presses: u32,
+    last_bounce_samples: u32,
+    max_bounce_samples: u32,
+    total_bounce_samples: u32,
+    ongoing_bounce_samples: u32,
+    stable_state: Option<SwitchState>,
+}
+
+impl BounceDiagnostics {
+    /// ## Description
+    /// Number of confirmed presses.
+    #[inline]
+    pub fn presses(&self) -> u32 {
+        self.presses
+    }
+
+    /// ## Description
+    /// Number of presses which bounced back to the released state without being confirmed.
+    #[inline]
+    pub fn aborted_presses(&self) -> u32 {
+        self.aborted_presses
+    }
+
+    /// ## Description
+    /// Number of bouncing samples of the last confirmed press.
+    #[inline]
+    pub fn last_bounce_samples(&self) -> u32 {
+        self.last_bounce_samples
+    }
+
+    /// ## Description
+    /// Largest number of bouncing samples of a confirmed press.
+    #[inline]
+    pub fn max_bounce_samples(&self) -> u32 {
+        self.max_bounce_samples
+    }
+
+    /// ## Description
+    /// Average number of bouncing samples per confirmed press (0 without any press).
+    #[inline]
+    pub fn average_bounce_samples(&self) -> u32 {
+        self.total_bounce_samples
+            .checked_div(self.presses)
+            .unwrap_or_default()
+    }
+
+    /// ## Description
+    /// Update the statistics with a new sample of the pin and the resulting state of the switch.
+    fn record(&mut self, pressed: bool, state: SwitchState) {
+        // The first stable level is the reference, nothing can bounce before it
+        let Some(stable_state) = self.stable_state else {
+            if state != SwitchState::Transition {
+                self.stable_state = Some(state);
+            }
+            return;
+        };
+        let disagrees = pressed != (stable_state == SwitchState::Pressed);
+
+        match state {
+            SwitchState::Transition if disagrees => self.count_bounce(),
+            SwitchState::Transition => (),
+            // A reading error does not tell anything about the bounce
+            SwitchState::Faulty => (),
+            // Change of the debounced level
+            _ if state != stable_state => {
+                if state == SwitchState::Pressed {
+                    self.presses = self.presses.saturating_add(1);
+                    self.last_bounce_samples = self.ongoing_bounce_samples;
+                    self.max_bounce_samples =
+                        self.max_bounce_samples.max(self.ongoing_bounce_samples);
+                    self.total_bounce_samples = self
+                        .total_bounce_samples
+                        .saturating_add(self.ongoing_bounce_samples);
+                }
+                self.ongoing_bounce_samples = 0;
+                self.stable_state = Some(state);
+            }
+            // Latched level kept while the pin bounces
+            _ if disagrees => self.count_bounce(),
+            // Back to the latched level
             _ => {
-                let was_pressed = self.switch.last_state != SwitchState::Pressed
-                    && current_state == SwitchState::Pressed;
-                self.switch.last_state = current_state;
-                Ok(was_pressed)
+                if self.ongoing_bounce_samples > 0 && state == SwitchState::Released {
+                    self.aborted_presses = self.aborted_presses.saturating_add(1);
+                }
+                self.ongoing_bounce_samples = 0;
             }
         }
     }
+
+    #[inline]
+    fn count_bounce(&mut self) {
+        self.ongoing_bounce_samples = self.ongoing_bounce_samples.saturating_add(1);
+    }
 }
 
 /*************************************/
@@ -311,6 +672,32 @@ mod tests {
         assert_eq!(SwitchState::Faulty, switch.get_current_state());
     }
 
+    #[inline(never)]
+    #[test]
+    fn test_switch_error_carries_pin_error() {
+        let pin = test_utils::MockedGpioPin {
+            state: PinState::High,
+            fault: true,
+        };
+        let mut switch = Switch::new(pin, PinState::Low);
+
+        // The error of the pin is kept
+        let error = switch.try_get_current_state().unwrap_err();
+        assert_eq!(SwitchError::ReadPinState(ErrorKind::Other), error);
+        assert_eq!(ErrorKind::Other, error.kind());
+        assert_eq!(Err(error), switch.has_been_pressed());
+        assert_eq!(Err(error), switch.has_been_released());
+
+        // Debouncer misconfigurations convert to switch errors
+        let error = debounce::Debouncer::<8>::try_new(0, 8)
+            .map_err(SwitchError::<ErrorKind>::from)
+            .unwrap_err();
+        assert_eq!(
+            SwitchError::Debounce(debounce::DebounceError::PressSamples),
+            error
+        );
+    }
+
     #[inline(never)]
     #[test]
     fn test_simple_switch_has_been_pressed() {
@@ -397,7 +784,8 @@ mod tests {
         // The 3th tick should consider a press
         assert_eq!(SwitchState::Pressed, db_switch.get_current_state());
 
-        //? Maintaining the button after that is no a pressed state anymore, is it what we want?
+        // The shift register debouncer only reports the press on the tick it is confirmed.
+        // Maintaining the button is a transition (see `LatchingDebouncer` for a stable pressed level).
         assert_eq!(SwitchState::Transition, db_switch.get_current_state());
 
         // Simulate the release of the pin
@@ -411,6 +799,58 @@ mod tests {
         assert_eq!(SwitchState::Released, db_switch.get_current_state());
     }
 
+    #[inline(never)]
+    #[test]
+    fn test_latching_debounced_switch_get_state() {
+        // Pull Up switch with Low level when pressed
+        let pressed_state = PinState::Low;
+        // Mocked pin with non faulty state and a reading that sets the switch as released.
+        let pin = test_utils::MockedGpioPin {
+            state: !pressed_state,
+            fault: false,
+        };
+        // Object under test
+        let mut db_switch =
+            Switch::new(pin, pressed_state).with_debounce(debounce::LatchingDebouncer::default());
+
+        // Should start in release state
+        assert_eq!(SwitchState::Released, db_switch.get_current_state());
+
+        // Set the pin to the pressed state to simulate press action
+        db_switch.switch.pin.state = PinState::Low;
+
+        // The first ticks are still considered released
+        for _ in 0..2 {
+            assert_eq!(SwitchState::Released, db_switch.get_current_state())
+        }
+
+        // The press is confirmed on the 3rd tick and reported as long as the button is held
+        for _ in 0..10 {
+            assert_eq!(SwitchState::Pressed, db_switch.get_current_state());
+        }
+
+        // The press is only reported once as an edge while the button is held
+        assert!(
+            db_switch
+                .has_been_pressed()
+                .expect("Problem when reading the pin")
+        );
+        assert!(
+            !db_switch
+                .has_been_pressed()
+                .expect("Problem when reading the pin")
+        );
+
+        // Simulate the release of the pin
+        db_switch.switch.pin.state = PinState::High;
+
+        // The button is considered held until the release is confirmed after 8 ticks
+        for _ in 0..7 {
+            assert_eq!(SwitchState::Pressed, db_switch.get_current_state())
+        }
+        assert_eq!(SwitchState::Released, db_switch.get_current_state());
+    }
+
     #[inline(never)]
     #[test]
     fn test_debounced_switch_has_been_pressed() {
@@ -458,4 +898,198 @@ mod tests {
                 .expect("Problem when reading the pin")
         );
     }
+
+    #[inline(never)]
+    #[test]
+    fn test_debounced_switch_diagnostics() {
+        // Pull Up switch with Low level when pressed
+        let pressed_state = PinState::Low;
+        // Mocked pin with non faulty state and a reading that sets the switch as released.
+        let pin = test_utils::MockedGpioPin {
+            state: !pressed_state,
+            fault: false,
+        };
+        // Object under test
+        let mut db_switch = Switch::new(pin, pressed_state)
+            .with_debounce(debounce::Debouncer::default())
+            .with_diagnostics();
+
+        // Released state is acknowledged
+        assert_eq!(SwitchState::Released, db_switch.get_current_state());
+
+        // Clean press: 2 transitional samples then pressed
+        db_switch.switch.pin.state = PinState::Low;
+        for _ in 0..3 {
+            db_switch.get_current_state();
+        }
+
+        // Release the switch until the released state is acknowledged
+        db_switch.switch.pin.state = PinState::High;
+        while db_switch.get_current_state() != SwitchState::Released {}
+
+        // Glitch shorter than a press which goes back to the released state
+        db_switch.switch.pin.state = PinState::Low;
+        db_switch.get_current_state();
+        db_switch.switch.pin.state = PinState::High;
+        while db_switch.get_current_state() != SwitchState::Released {}
+
+        let diagnostics = db_switch.diagnostics().expect("Diagnostics are enabled");
+        assert_eq!(1, diagnostics.presses());
+        assert_eq!(1, diagnostics.aborted_presses());
+        assert_eq!(2, diagnostics.last_bounce_samples());
+        assert_eq!(2, diagnostics.max_bounce_samples());
+        assert_eq!(2, diagnostics.average_bounce_samples());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_latching_switch_diagnostics() {
+        // Pull Up switch with Low level when pressed, initially released
+        let pin = test_utils::MockedGpioPin {
+            state: PinState::High,
+            fault: false,
+        };
+        // The latching debouncer never reports `Transition`
+        let mut db_switch = Switch::new(pin, PinState::Low)
+            .with_debounce(debounce::LatchingDebouncer::default())
+            .with_diagnostics();
+        db_switch.get_current_state();
+
+        // Bouncing press: pressed, released, then 3 pressed samples
+        for level in [PinState::Low, PinState::High, PinState::Low, PinState::Low] {
+            db_switch.switch.pin.state = level;
+            assert_eq!(SwitchState::Released, db_switch.get_current_state());
+        }
+        db_switch.switch.pin.state = PinState::Low;
+        assert_eq!(SwitchState::Pressed, db_switch.get_current_state());
+
+        let diagnostics = db_switch.diagnostics().expect("Diagnostics are enabled");
+        assert_eq!(1, diagnostics.presses());
+        assert_eq!(1, diagnostics.aborted_presses());
+        assert_eq!(2, diagnostics.last_bounce_samples());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_switch_has_been_released_with_duration() {
+        let clock = test_utils::MockedClock::default();
+        // Pull Up switch with Low level when pressed
+        let pressed_state = PinState::Low;
+        // Mocked pin with non faulty state and a reading that sets the switch as released.
+        let pin = test_utils::MockedGpioPin {
+            state: !pressed_state,
+            fault: false,
+        };
+        // Object under test
+        let mut switch = Switch::new(pin, pressed_state).with_clock(&clock);
+
+        // No press yet
+        assert!(
+            !switch
+                .has_been_released()
+                .expect("Problem when reading the pin")
+        );
+        assert_eq!(None, switch.last_press_duration());
+
+        // Press and hold for 1.5 s
+        switch.pin.state = PinState::Low;
+        assert!(
+            switch
+                .has_been_pressed()
+                .expect("Problem when reading the pin")
+        );
+        clock.advance(1500);
+        assert!(
+            !switch
+                .has_been_released()
+                .expect("Problem when reading the pin")
+        );
+
+        // Release: the pressed edge check does not consume the released edge
+        switch.pin.state = PinState::High;
+        assert!(
+            !switch
+                .has_been_pressed()
+                .expect("Problem when reading the pin")
+        );
+        assert!(
+            switch
+                .has_been_released()
+                .expect("Problem when reading the pin")
+        );
+        assert!(
+            !switch
+                .has_been_released()
+                .expect("Problem when reading the pin")
+        );
+        assert_eq!(Some(1500), switch.last_press_duration());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_debounced_switch_has_been_released() {
+        // Pull Up switch with Low level when pressed
+        let pressed_state = PinState::Low;
+        // Mocked pin with non faulty state and a reading that sets the switch as released.
+        let pin = test_utils::MockedGpioPin {
+            state: !pressed_state,
+            fault: false,
+        };
+        // Object under test, without clock
+        let mut db_switch =
+            Switch::new(pin, pressed_state).with_debounce(debounce::Debouncer::default());
+
+        // Press until acknowledged
+        db_switch.switch.pin.state = PinState::Low;
+        while !db_switch
+            .has_been_pressed()
+            .expect("Problem when reading the pin")
+        {}
+
+        // Release: 7 ticks of transition then the release is acknowledged
+        db_switch.switch.pin.state = PinState::High;
+        for _ in 0..7 {
+            assert!(
+                !db_switch
+                    .has_been_released()
+                    .expect("Problem when reading the pin")
+            );
+        }
+        assert!(
+            db_switch
+                .has_been_released()
+                .expect("Problem when reading the pin")
+        );
+
+        // No clock attached, the duration is unknown
+        assert_eq!(None, db_switch.last_press_duration());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_pressable_defaults() {
+        // A switch without clock, implementing only the required methods
+        struct PressOnly;
+        impl Pressable for PressOnly {
+            type PinError = embedded_hal::digital::ErrorKind;
+
+            fn try_get_current_state(
+                &mut self,
+            ) -> Result<SwitchState, SwitchError<Self::PinError>> {
+                Ok(SwitchState::Pressed)
+            }
+
+            fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+                Ok(true)
+            }
+
+            fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+                Ok(false)
+            }
+        }
+
+        let mut switch = PressOnly;
+        assert_eq!(Ok(true), switch.has_been_pressed());
+        assert_eq!(None, switch.last_press_duration());
+    }
 }
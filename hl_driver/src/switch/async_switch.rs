@@ -0,0 +1,242 @@
+use embedded_hal::digital::{InputPin, PinState};
+use embedded_hal_async::{delay::DelayNs, digital::Wait};
+
+use crate::debounce::Debounce;
+use crate::switch::{DebouncedSwitch, Pressable, SwitchError, SwitchState};
+use crate::time::{Monotonic, NoClock};
+
+/// ## Description
+///
+/// A debounced switch which can be awaited instead of polled.
+/// Implements the Pressable trait.
+///
+/// While the switch is stable, the task sleeps until the pin changes level (`embedded_hal_async::digital::Wait`).
+/// Once the pin changed, it is sampled every `sample_period_us` through the debouncer until the
+/// debounced level matches the pin level again.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct AsyncSwitch<PIN, D, DELAY, C = NoClock>
+where
+    PIN: InputPin + Wait,
+    D: Debounce,
+    DELAY: DelayNs,
+    C: Monotonic,
+{
+    switch: DebouncedSwitch<PIN, D, C>,
+    delay: DELAY,
+    sample_period_us: u32,
+}
+
+impl<PIN, D, DELAY, C> AsyncSwitch<PIN, D, DELAY, C>
+where
+    PIN: InputPin + Wait,
+    D: Debounce,
+    DELAY: DelayNs,
+    C: Monotonic,
+{
+    /// ## Description
+    ///
+    /// Create an awaitable switch from a debounced switch.
+    ///
+    /// ## Parameters
+    /// - `switch`: a debounced switch whose pin implements `embedded_hal_async::digital::Wait`
+    /// - `delay`: an async delay implementing `embedded_hal_async::delay::DelayNs`
+    /// - `sample_period_us`: period at which the pin is sampled while debouncing
+    ///
+    /// ## Return
+    /// - AsyncSwitch
+    pub fn new(switch: DebouncedSwitch<PIN, D, C>, delay: DELAY, sample_period_us: u32) -> Self {
+        AsyncSwitch {
+            switch,
+            delay,
+            sample_period_us,
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Wait until the switch has been pressed (debounced pressed edge).
+    ///
+    /// ## Return
+    /// *Result<(), SwitchError>*
+    /// - `SwitchError::ReadPinState`: an error occured when reading or waiting for the gpio pin of the switch
+    pub async fn wait_for_press(&mut self) -> Result<(), SwitchError<PIN::Error>> {
+        while !self.switch.has_been_pressed()? {
+            self.settle().await?;
+        }
+        Ok(())
+    }
+
+    /// ## Description
+    ///
+    /// Wait until the switch has been released (debounced released edge).
+    ///
+    /// ## Return
+    /// *Result<(), SwitchError>*
+    /// - `SwitchError::ReadPinState`: an error occured when reading or waiting for the gpio pin of the switch
+    pub async fn wait_for_release(&mut self) -> Result<(), SwitchError<PIN::Error>> {
+        while !self.switch.has_been_released()? {
+            self.settle().await?;
+        }
+        Ok(())
+    }
+
+    /// ## Description
+    ///
+    /// Wait before the next sample: a sample period while the debouncer is catching up with the pin,
+    /// until the pin leaves the debounced level otherwise.
+    async fn settle(&mut self) -> Result<(), SwitchError<PIN::Error>> {
+        let pressed_state = self.switch.switch.pressed_state;
+        let pin_pressed = self
+            .switch
+            .switch
+            .pin
+            .is_high()
+            .map_err(SwitchError::ReadPinState)?
+            == bool::from(pressed_state);
+        let debounced_pressed = self.switch.switch.last_state == SwitchState::Pressed;
+
+        if pin_pressed != debounced_pressed {
+            self.delay.delay_us(self.sample_period_us).await;
+            return Ok(());
+        }
+
+        // Stable: sleep until the pin reaches the opposite level
+        let awaited = if debounced_pressed {
+            !pressed_state
+        } else {
+            pressed_state
+        };
+        let pin = &mut self.switch.switch.pin;
+        match awaited {
+            PinState::High => pin.wait_for_high().await,
+            PinState::Low => pin.wait_for_low().await,
+        }
+        .map_err(SwitchError::ReadPinState)
+    }
+}
+
+impl<PIN, D, DELAY, C> Pressable for AsyncSwitch<PIN, D, DELAY, C>
+where
+    PIN: InputPin + Wait,
+    D: Debounce,
+    DELAY: DelayNs,
+    C: Monotonic,
+{
+    type PinError = PIN::Error;
+
+    /// ## Description
+    ///
+    /// (Forwards the `Pressable` implementation of the debounced switch)
+    #[inline]
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
+        self.switch.try_get_current_state()
+    }
+
+    /// ## Description
+    ///
+    /// (Forwards the `Pressable` implementation of the debounced switch)
+    #[inline]
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        self.switch.has_been_pressed()
+    }
+
+    /// ## Description
+    ///
+    /// (Forwards the `Pressable` implementation of the debounced switch)
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        self.switch.has_been_released()
+    }
+
+    /// ## Description
+    ///
+    /// (Forwards the `Pressable` implementation of the debounced switch)
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        self.switch.last_press_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debounce::{Debouncer, LatchingDebouncer};
+    use crate::switch::Switch;
+    use crate::test_utils::{MockedDelay, MockedGpioPin, MockedWaitPin, block_on};
+    use embedded_hal::digital::ErrorKind;
+
+    #[inline(never)]
+    #[test]
+    fn test_async_switch_wait_for_press_and_release() {
+        // Pull Up switch with Low level when pressed, initially released
+        let pin = MockedGpioPin {
+            state: PinState::High,
+            fault: false,
+        };
+        let switch = Switch::new(pin, PinState::Low).with_debounce(LatchingDebouncer::default());
+        let mut async_switch = AsyncSwitch::new(switch, MockedDelay::default(), 1000);
+
+        // The mocked pin reaches the awaited level immediately, then the press is debounced
+        block_on(async_switch.wait_for_press()).expect("Problem when reading the pin");
+        assert_eq!(PinState::Low, async_switch.switch.switch.pin.state);
+        // 3 samples to latch the press: 2 sample periods
+        assert_eq!(2_000_000, async_switch.delay.elapsed_ns);
+
+        // 8 samples to latch the release: 7 sample periods
+        block_on(async_switch.wait_for_release()).expect("Problem when reading the pin");
+        assert_eq!(PinState::High, async_switch.switch.switch.pin.state);
+        assert_eq!(9_000_000, async_switch.delay.elapsed_ns);
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_async_switch_sleeps_while_held() {
+        // Pull Up switch with Low level when pressed, initially pressed
+        let pin = MockedWaitPin {
+            pin: MockedGpioPin {
+                state: PinState::Low,
+                fault: false,
+            },
+            waits: 0,
+        };
+        // The shift register debouncer reports a transition while the switch is held
+        let switch = Switch::new(pin, PinState::Low).with_debounce(Debouncer::default());
+        let mut async_switch = AsyncSwitch::new(switch, MockedDelay::default(), 1000);
+
+        // The press is debounced without waiting for the pin
+        block_on(async_switch.wait_for_press()).expect("Problem when reading the pin");
+        assert_eq!(0, async_switch.switch.switch.pin.waits);
+        assert_eq!(2_000_000, async_switch.delay.elapsed_ns);
+
+        // While held, a single wait for the release and no sampling:
+        // the only delays are the 7 sample periods debouncing the release
+        block_on(async_switch.wait_for_release()).expect("Problem when reading the pin");
+        assert_eq!(1, async_switch.switch.switch.pin.waits);
+        assert_eq!(9_000_000, async_switch.delay.elapsed_ns);
+
+        // Same for the next press, once released
+        block_on(async_switch.wait_for_press()).expect("Problem when reading the pin");
+        assert_eq!(2, async_switch.switch.switch.pin.waits);
+        assert_eq!(PinState::Low, async_switch.switch.switch.pin.pin.state);
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_async_switch_faulty() {
+        let pin = MockedGpioPin {
+            state: PinState::High,
+            fault: true,
+        };
+        let switch = Switch::new(pin, PinState::Low).with_debounce(LatchingDebouncer::default());
+        let mut async_switch = AsyncSwitch::new(switch, MockedDelay::default(), 1000);
+
+        assert_eq!(
+            Err(SwitchError::ReadPinState(ErrorKind::Other)),
+            block_on(async_switch.wait_for_press())
+        );
+    }
+}
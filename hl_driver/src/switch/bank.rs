@@ -0,0 +1,139 @@
+use core::convert::Infallible;
+
+use crate::debounce::{DebounceBank, DebounceState, Register};
+use crate::switch::{Pressable, SwitchError, SwitchState};
+
+/// ## Description
+///
+/// Single input of a `DebounceBank` seen as a switch.
+/// Implements the Pressable trait.
+///
+/// The switch reads the result of the last update of the bank, it does not sample anything itself.
+/// The bank is updated through a shared reference, so the switch can be kept across the updates.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct BankSwitch<'a, R>
+where
+    R: Register,
+{
+    bank: &'a DebounceBank<R>,
+    index: u8,
+}
+
+impl<'a, R> BankSwitch<'a, R>
+where
+    R: Register,
+{
+    /// ## Description
+    ///
+    /// Use a single input of a bank as a switch.
+    ///
+    /// ## Parameters
+    /// - `bank`: the bank debouncing the input
+    /// - `index`: bit of the input in the bank
+    ///
+    /// ## Panics
+    /// If the bank has no such input.
+    ///
+    /// ## Return
+    /// - BankSwitch
+    pub fn new(bank: &'a DebounceBank<R>, index: u8) -> Self {
+        assert!((index as u32) < R::BITS, "The bank has no such input");
+        BankSwitch { bank, index }
+    }
+}
+
+impl<R> Pressable for BankSwitch<'_, R>
+where
+    R: Register,
+{
+    type PinError = Infallible;
+
+    /// ## Description
+    ///
+    /// Return the debounced state of the input after the last update of the bank.
+    ///
+    /// ## Return
+    /// *Result<SwitchState, SwitchError>*
+    /// - `SwitchState`: Pressed, Released or Transition (debouncing is ongoing)
+    ///
+    /// The samples are read by the owner of the bank, so this never fails.
+    #[inline]
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
+        Ok(match self.bank.get_state(self.index) {
+            DebounceState::Loaded => SwitchState::Pressed,
+            DebounceState::Transition => SwitchState::Transition,
+            DebounceState::Unloaded => SwitchState::Released,
+        })
+    }
+
+    /// ## Description
+    ///
+    /// Return if the input has been pressed since the last use of this method
+    /// (on any switch of the same input).
+    ///
+    /// ## Return
+    /// *Result<bool, SwitchError>*
+    /// - `bool`: `true` if the switch has been pressed, `false` otherwise
+    #[inline]
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        Ok(self.bank.take_pressed(self.index))
+    }
+
+    /// ## Description
+    ///
+    /// Return if the input has been released since the last use of this method
+    /// (on any switch of the same input).
+    ///
+    /// ## Return
+    /// *Result<bool, SwitchError>*
+    /// - `bool`: `true` if the switch has been released, `false` otherwise
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        Ok(self.bank.take_released(self.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[inline(never)]
+    #[test]
+    fn test_bank_switches() {
+        let bank = DebounceBank::<u8>::new();
+        let mut first = BankSwitch::new(&bank, 1);
+        let mut second = BankSwitch::new(&bank, 2);
+        let mut third = BankSwitch::new(&bank, 3);
+
+        // The switches are kept across the updates
+        for _ in 0..4 {
+            bank.update(0b0000_0110);
+        }
+
+        // Each switch reports its own pressed edge once
+        assert_eq!(SwitchState::Pressed, first.get_current_state());
+        assert!(first.has_been_pressed().unwrap());
+        assert!(!first.has_been_pressed().unwrap());
+        assert!(second.has_been_pressed().unwrap());
+        assert_eq!(SwitchState::Released, third.get_current_state());
+        assert!(!third.has_been_pressed().unwrap());
+
+        for _ in 0..4 {
+            bank.update(0b0000_0000);
+        }
+        assert!(first.has_been_released().unwrap());
+        assert!(!third.has_been_released().unwrap());
+    }
+
+    #[inline(never)]
+    #[test]
+    #[should_panic(expected = "The bank has no such input")]
+    fn test_bank_switch_out_of_range() {
+        let bank = DebounceBank::<u8>::new();
+        let _ = BankSwitch::new(&bank, 8);
+    }
+}
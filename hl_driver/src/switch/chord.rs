@@ -0,0 +1,275 @@
+use crate::switch::{Pressable, SwitchError, SwitchState};
+use crate::time::Monotonic;
+
+// Default chord timings
+const DEFAULT_WINDOW_MS: u32 = 200;
+const DEFAULT_HOLD_MS: u32 = 3000;
+
+/// ## Description
+///
+/// Events reported by a `ChordDetector`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChordEvent {
+    Pressed, // All the switches have been pressed within the simultaneity window
+    Held,    // The chord has been held for the hold duration
+    Released { held_ms: u64 }, // One of the switches of the chord has been released
+}
+
+/// ## Description
+///
+/// Timing windows used to recognize chords, in milliseconds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ChordTiming {
+    /// Maximum delay between the first and the last switch press of a chord
+    pub window_ms: u32,
+    /// Duration after which a chord is reported as held
+    pub hold_ms: u32,
+}
+
+impl Default for ChordTiming {
+    fn default() -> Self {
+        ChordTiming {
+            window_ms: DEFAULT_WINDOW_MS,
+            hold_ms: DEFAULT_HOLD_MS,
+        }
+    }
+}
+
+/// ## Description
+///
+/// Recognition progress of a chord.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Phase {
+    Idle,                              // No switch pressed
+    Arming { since: u64 },             // Some switches pressed, waiting for the others
+    Active { since: u64, held: bool }, // All switches pressed
+    Rejected,                          // Not a chord, waiting for all switches to be released
+}
+
+/// ## Description
+///
+/// Detect multi-button combinations (chords) over several switches implementing the `Pressable` trait.
+///
+/// A chord is recognized when all the switches are pressed within the simultaneity window.
+/// Once a chord has been released or rejected, all the switches have to be released before a new
+/// chord can be recognized.
+/// Switches of different types can be combined through `&mut dyn Pressable<PinError = E>`,
+/// as long as they share the same pin error type.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct ChordDetector<SW, C, const N: usize>
+where
+    SW: Pressable,
+    C: Monotonic,
+{
+    switches: [SW; N],
+    clock: C,
+    timing: ChordTiming,
+    levels: [bool; N],
+    phase: Phase,
+}
+
+impl<SW, C, const N: usize> ChordDetector<SW, C, N>
+where
+    SW: Pressable,
+    C: Monotonic,
+{
+    /// ## Description
+    ///
+    /// Create a chord detector over a set of switches.
+    ///
+    /// ## Parameters
+    /// - `switches`: the switches which have to be pressed together
+    /// - `clock`: a clock implementing `hl_driver::time::Monotonic`
+    /// - `timing`: the timing windows of the chord
+    ///
+    /// ## Return
+    /// - ChordDetector
+    pub fn new(switches: [SW; N], clock: C, timing: ChordTiming) -> Self {
+        ChordDetector {
+            switches,
+            clock,
+            timing,
+            levels: [false; N],
+            phase: Phase::Idle,
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Access the switches of the chord, for instance to use them individually.
+    ///
+    /// ## Return
+    /// - `&mut [SW; N]`: the switches of the chord
+    pub fn switches_mut(&mut self) -> &mut [SW; N] {
+        &mut self.switches
+    }
+
+    /// ## Description
+    ///
+    /// Give back the switches used by the detector.
+    ///
+    /// ## Return
+    /// - `[SW; N]`: the switches of the chord
+    pub fn into_inner(self) -> [SW; N] {
+        self.switches
+    }
+
+    /// ## Description
+    ///
+    /// Sample all the switches and update the chord recognition.
+    ///
+    /// ## Return
+    /// *Result<Option<ChordEvent>, SwitchError>*
+    /// - `Option<ChordEvent>`: the chord event which happened during this poll, if any
+    /// - `SwitchError`: the error reported by one of the switches (e.g. `ReadPinState`)
+    pub fn poll(&mut self) -> Result<Option<ChordEvent>, SwitchError<SW::PinError>> {
+        for (switch, level) in self.switches.iter_mut().zip(self.levels.iter_mut()) {
+            match switch.try_get_current_state()? {
+                SwitchState::Pressed => *level = true,
+                SwitchState::Released => *level = false,
+                // Keep the last known level while debouncing
+                SwitchState::Transition | SwitchState::Faulty => (),
+            }
+        }
+        let all = self.levels.iter().all(|level| *level);
+        let any = self.levels.iter().any(|level| *level);
+        let now = self.clock.now_ms();
+
+        let (phase, event) = match self.phase {
+            Phase::Idle | Phase::Arming { .. } if !any => (Phase::Idle, None),
+            Phase::Idle => self.arm(now, now, all),
+            Phase::Arming { since } => self.arm(since, now, all),
+            Phase::Active { since, .. } if !all => (
+                Phase::Rejected,
+                Some(ChordEvent::Released {
+                    held_ms: now - since,
+                }),
+            ),
+            Phase::Active { since, held: false } if now - since >= self.timing.hold_ms as u64 => {
+                (Phase::Active { since, held: true }, Some(ChordEvent::Held))
+            }
+            Phase::Active { .. } => (self.phase, None),
+            Phase::Rejected if !any => (Phase::Idle, None),
+            Phase::Rejected => (Phase::Rejected, None),
+        };
+
+        self.phase = phase;
+        Ok(event)
+    }
+
+    /// ## Description
+    ///
+    /// Progress of a chord while some of its switches are pressed.
+    fn arm(&self, since: u64, now: u64, all: bool) -> (Phase, Option<ChordEvent>) {
+        let in_window = now - since <= self.timing.window_ms as u64;
+        match (all, in_window) {
+            (true, true) => (
+                Phase::Active {
+                    since: now,
+                    held: false,
+                },
+                Some(ChordEvent::Pressed),
+            ),
+            (false, true) => (Phase::Arming { since }, None),
+            (_, false) => (Phase::Rejected, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockedClock, pull_up_switch};
+    use embedded_hal::digital::ErrorKind;
+
+    #[inline(never)]
+    #[test]
+    fn test_chord_pressed_held_released() {
+        let clock = MockedClock::default();
+        let mut detector = ChordDetector::new(
+            [pull_up_switch(), pull_up_switch()],
+            &clock,
+            ChordTiming::default(),
+        );
+
+        // First switch pressed
+        detector.switches_mut()[0].pin.set_pressed(true);
+        assert_eq!(Ok(None), detector.poll());
+
+        // Second switch pressed within the window
+        clock.advance(150);
+        detector.switches_mut()[1].pin.set_pressed(true);
+        assert_eq!(Ok(Some(ChordEvent::Pressed)), detector.poll());
+
+        // Held for 3 s
+        clock.advance(2999);
+        assert_eq!(Ok(None), detector.poll());
+        clock.advance(1);
+        assert_eq!(Ok(Some(ChordEvent::Held)), detector.poll());
+        clock.advance(500);
+        assert_eq!(Ok(None), detector.poll());
+
+        // Releasing one switch releases the chord
+        detector.switches_mut()[1].pin.set_pressed(false);
+        assert_eq!(
+            Ok(Some(ChordEvent::Released { held_ms: 3500 })),
+            detector.poll()
+        );
+
+        // Pressing it again does not start a new chord until all switches are released
+        detector.switches_mut()[1].pin.set_pressed(true);
+        assert_eq!(Ok(None), detector.poll());
+        detector.switches_mut()[0].pin.set_pressed(false);
+        detector.switches_mut()[1].pin.set_pressed(false);
+        assert_eq!(Ok(None), detector.poll());
+        detector.switches_mut()[0].pin.set_pressed(true);
+        detector.switches_mut()[1].pin.set_pressed(true);
+        assert_eq!(Ok(Some(ChordEvent::Pressed)), detector.poll());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_chord_outside_window() {
+        let clock = MockedClock::default();
+        let mut detector = ChordDetector::new(
+            [pull_up_switch(), pull_up_switch()],
+            &clock,
+            ChordTiming::default(),
+        );
+
+        detector.switches_mut()[0].pin.set_pressed(true);
+        assert_eq!(Ok(None), detector.poll());
+
+        // Second switch pressed too late
+        clock.advance(250);
+        detector.switches_mut()[1].pin.set_pressed(true);
+        assert_eq!(Ok(None), detector.poll());
+        clock.advance(5000);
+        assert_eq!(Ok(None), detector.poll());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_chord_heterogeneous_switches() {
+        let clock = MockedClock::default();
+        let mut simple = pull_up_switch();
+        let mut debounced =
+            pull_up_switch().with_debounce(crate::debounce::LatchingDebouncer::default());
+        simple.pin.set_pressed(true);
+        debounced.switch.pin.set_pressed(true);
+
+        let switches: [&mut dyn Pressable<PinError = ErrorKind>; 2] = [&mut simple, &mut debounced];
+        let mut detector = ChordDetector::new(switches, &clock, ChordTiming::default());
+
+        // The debounced switch needs 3 ticks to be pressed
+        for _ in 0..2 {
+            assert_eq!(Ok(None), detector.poll());
+            clock.advance(10);
+        }
+        assert_eq!(Ok(Some(ChordEvent::Pressed)), detector.poll());
+    }
+}
@@ -0,0 +1,370 @@
+use crate::switch::{Pressable, SwitchError, SwitchState};
+use crate::time::Monotonic;
+
+// Default gesture timings
+const DEFAULT_MULTI_CLICK_MS: u32 = 300;
+const DEFAULT_LONG_PRESS_MS: u32 = 800;
+const DEFAULT_REPEAT_MS: u32 = 200;
+
+/// ## Description
+///
+/// Gestures recognized by a `ClickDetector`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ButtonEvent {
+    Click,           // A single short press, reported once the multi click window expired
+    DoubleClick,     // Two short presses, reported once the multi click window expired
+    TripleClick,     // Three short presses, reported on the third release
+    LongPress,       // The button has been held for the long press duration
+    LongPressRepeat, // The button is still held, reported periodically after a long press
+    Release,         // The button has been released after a long press
+}
+
+/// ## Description
+///
+/// Timing windows used to recognize gestures, in milliseconds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct GestureTiming {
+    /// Maximum delay between a release and the next press to be part of the same multi click
+    pub multi_click_ms: u32,
+    /// Duration after which a held button is a long press
+    pub long_press_ms: u32,
+    /// Period of the repeat events while a long press is held
+    pub repeat_ms: u32,
+}
+
+impl Default for GestureTiming {
+    fn default() -> Self {
+        GestureTiming {
+            multi_click_ms: DEFAULT_MULTI_CLICK_MS,
+            long_press_ms: DEFAULT_LONG_PRESS_MS,
+            repeat_ms: DEFAULT_REPEAT_MS,
+        }
+    }
+}
+
+/// ## Description
+///
+/// Gesture layer over any switch implementing the `Pressable` trait.
+/// Recognizes single, double and triple clicks as well as long presses with repeat.
+///
+/// `Transition` states reported by debounced switches keep the last known level,
+/// so the detector works with any debouncer. A debouncer latching the pressed level
+/// (e.g. `hl_driver::debounce::LatchingDebouncer`) gives the most accurate hold durations.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct ClickDetector<SW, C>
+where
+    SW: Pressable,
+    C: Monotonic,
+{
+    switch: SW,
+    clock: C,
+    timing: GestureTiming,
+    pressed: bool,
+    pressed_at: u64,
+    released_at: u64,
+    clicks: u8,
+    long_press: bool,
+    last_repeat: u64,
+}
+
+impl<SW, C> ClickDetector<SW, C>
+where
+    SW: Pressable,
+    C: Monotonic,
+{
+    /// ## Description
+    ///
+    /// Create a gesture detector over a switch.
+    ///
+    /// ## Parameters
+    /// - `switch`: a switch implementing `hl_driver::switch::Pressable`
+    /// - `clock`: a clock implementing `hl_driver::time::Monotonic`
+    /// - `timing`: the timing windows of the gestures
+    ///
+    /// ## Return
+    /// - ClickDetector
+    pub fn new(switch: SW, clock: C, timing: GestureTiming) -> Self {
+        ClickDetector {
+            switch,
+            clock,
+            timing,
+            pressed: false,
+            pressed_at: 0,
+            released_at: 0,
+            clicks: 0,
+            long_press: false,
+            last_repeat: 0,
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Give back the switch used by the detector.
+    ///
+    /// ## Return
+    /// - `SW`: the wrapped switch
+    pub fn into_inner(self) -> SW {
+        self.switch
+    }
+
+    /// ## Description
+    ///
+    /// Sample the switch and update the gesture recognition.
+    /// Has to be called periodically, faster than the shortest timing window.
+    ///
+    /// ## Return
+    /// *Result<Option<ButtonEvent>, SwitchError>*
+    /// - `Option<ButtonEvent>`: the gesture recognized during this poll, if any
+    /// - `SwitchError`: the error reported by the switch (e.g. `ReadPinState`)
+    pub fn poll(&mut self) -> Result<Option<ButtonEvent>, SwitchError<SW::PinError>> {
+        let pressed = match self.switch.try_get_current_state()? {
+            SwitchState::Pressed => true,
+            SwitchState::Released => false,
+            SwitchState::Transition | SwitchState::Faulty => self.pressed,
+        };
+        let now = self.clock.now_ms();
+
+        let event = match (self.pressed, pressed) {
+            // Press edge, a press after the multi click window starts a new gesture
+            (false, true) => {
+                self.pressed_at = now;
+                let expired = self.take_expired_clicks(now);
+                self.clicks = self.clicks.saturating_add(1);
+                expired
+            }
+            // Release edge
+            (true, false) => {
+                self.released_at = now;
+                if self.long_press {
+                    self.long_press = false;
+                    Some(ButtonEvent::Release)
+                } else if self.clicks >= 3 {
+                    self.clicks = 0;
+                    Some(ButtonEvent::TripleClick)
+                } else {
+                    None
+                }
+            }
+            // Held
+            (true, true) => {
+                if !self.long_press {
+                    if now - self.pressed_at < self.timing.long_press_ms as u64 {
+                        None
+                    } else if self.clicks > 1 {
+                        // The clicks before this press are reported first, the long press on the next poll
+                        let earlier = self.clicks - 1;
+                        self.clicks = 1;
+                        Some(Self::clicks_event(earlier))
+                    } else {
+                        self.long_press = true;
+                        self.clicks = 0;
+                        self.last_repeat = now;
+                        Some(ButtonEvent::LongPress)
+                    }
+                } else if now - self.last_repeat >= self.timing.repeat_ms as u64 {
+                    self.last_repeat = now;
+                    Some(ButtonEvent::LongPressRepeat)
+                } else {
+                    None
+                }
+            }
+            // Idle, pending clicks are reported once the multi click window expired
+            (false, false) => self.take_expired_clicks(now),
+        };
+
+        self.pressed = pressed;
+        Ok(event)
+    }
+
+    /// ## Description
+    ///
+    /// Report and clear the pending clicks once the multi click window expired.
+    fn take_expired_clicks(&mut self, now: u64) -> Option<ButtonEvent> {
+        if self.clicks == 0 || now - self.released_at < self.timing.multi_click_ms as u64 {
+            return None;
+        }
+        Some(Self::clicks_event(core::mem::take(&mut self.clicks)))
+    }
+
+    /// ## Description
+    ///
+    /// Event reporting a number of clicks below a triple click.
+    fn clicks_event(clicks: u8) -> ButtonEvent {
+        match clicks {
+            1 => ButtonEvent::Click,
+            _ => ButtonEvent::DoubleClick,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockedClock, pull_up_switch};
+    use embedded_hal::digital::ErrorKind;
+
+    // Poll the detector every 10 ms for the given duration and collect the events
+    fn run<SW: Pressable>(
+        detector: &mut ClickDetector<SW, &MockedClock>,
+        clock: &MockedClock,
+        duration_ms: u64,
+        events: &mut [Option<ButtonEvent>; 16],
+    ) {
+        for _ in 0..duration_ms / 10 {
+            if let Some(event) = detector.poll().expect("Problem when reading the pin") {
+                let slot = events
+                    .iter_mut()
+                    .find(|slot| slot.is_none())
+                    .expect("Too many events");
+                *slot = Some(event);
+            }
+            clock.advance(10);
+        }
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_single_click() {
+        let clock = MockedClock::default();
+        let mut detector = ClickDetector::new(pull_up_switch(), &clock, GestureTiming::default());
+        let mut events = [None; 16];
+
+        detector.switch.pin.set_pressed(true);
+        run(&mut detector, &clock, 100, &mut events);
+        detector.switch.pin.set_pressed(false);
+        // Nothing reported while the multi click window is open
+        run(&mut detector, &clock, 200, &mut events);
+        assert_eq!(None, events[0]);
+        run(&mut detector, &clock, 200, &mut events);
+
+        assert_eq!([Some(ButtonEvent::Click), None], events[..2]);
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_double_and_triple_click() {
+        let clock = MockedClock::default();
+        let mut detector = ClickDetector::new(pull_up_switch(), &clock, GestureTiming::default());
+        let mut events = [None; 16];
+
+        // Double click
+        for _ in 0..2 {
+            detector.switch.pin.set_pressed(true);
+            run(&mut detector, &clock, 100, &mut events);
+            detector.switch.pin.set_pressed(false);
+            run(&mut detector, &clock, 100, &mut events);
+        }
+        run(&mut detector, &clock, 300, &mut events);
+
+        // Triple click, reported on the last release
+        for _ in 0..3 {
+            detector.switch.pin.set_pressed(true);
+            run(&mut detector, &clock, 100, &mut events);
+            detector.switch.pin.set_pressed(false);
+            run(&mut detector, &clock, 10, &mut events);
+        }
+
+        assert_eq!(
+            [
+                Some(ButtonEvent::DoubleClick),
+                Some(ButtonEvent::TripleClick),
+                None
+            ],
+            events[..3]
+        );
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_late_press_starts_new_gesture() {
+        let clock = MockedClock::default();
+        let mut detector = ClickDetector::new(pull_up_switch(), &clock, GestureTiming::default());
+
+        // Click, then the detector is not polled during the multi click window
+        detector.switch.pin.set_pressed(true);
+        assert_eq!(Ok(None), detector.poll());
+        clock.advance(100);
+        detector.switch.pin.set_pressed(false);
+        assert_eq!(Ok(None), detector.poll());
+        clock.advance(500);
+
+        // The late press reports the pending click instead of making a double click
+        detector.switch.pin.set_pressed(true);
+        assert_eq!(Ok(Some(ButtonEvent::Click)), detector.poll());
+        clock.advance(100);
+        detector.switch.pin.set_pressed(false);
+        assert_eq!(Ok(None), detector.poll());
+        clock.advance(500);
+        assert_eq!(Ok(Some(ButtonEvent::Click)), detector.poll());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_click_then_long_press() {
+        let clock = MockedClock::default();
+        let mut detector = ClickDetector::new(pull_up_switch(), &clock, GestureTiming::default());
+        let mut events = [None; 16];
+
+        // Click, then press and hold within the multi click window
+        detector.switch.pin.set_pressed(true);
+        run(&mut detector, &clock, 100, &mut events);
+        detector.switch.pin.set_pressed(false);
+        run(&mut detector, &clock, 100, &mut events);
+        detector.switch.pin.set_pressed(true);
+        run(&mut detector, &clock, 900, &mut events);
+        detector.switch.pin.set_pressed(false);
+        run(&mut detector, &clock, 500, &mut events);
+
+        assert_eq!(
+            [
+                Some(ButtonEvent::Click),
+                Some(ButtonEvent::LongPress),
+                Some(ButtonEvent::Release),
+                None
+            ],
+            events[..4]
+        );
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_long_press_with_repeat() {
+        let clock = MockedClock::default();
+        let mut detector = ClickDetector::new(pull_up_switch(), &clock, GestureTiming::default());
+        let mut events = [None; 16];
+
+        // Held for 1.3 s: long press after 800 ms and repeats every 200 ms
+        detector.switch.pin.set_pressed(true);
+        run(&mut detector, &clock, 1300, &mut events);
+        detector.switch.pin.set_pressed(false);
+        run(&mut detector, &clock, 500, &mut events);
+
+        assert_eq!(
+            [
+                Some(ButtonEvent::LongPress),
+                Some(ButtonEvent::LongPressRepeat),
+                Some(ButtonEvent::LongPressRepeat),
+                Some(ButtonEvent::Release),
+                None
+            ],
+            events[..5]
+        );
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_faulty_switch() {
+        let clock = MockedClock::default();
+        let mut detector = ClickDetector::new(pull_up_switch(), &clock, GestureTiming::default());
+
+        detector.switch.pin.fault = true;
+        assert_eq!(
+            Err(SwitchError::ReadPinState(ErrorKind::Other)),
+            detector.poll()
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use crate::switch::{Pressable, SwitchError, SwitchState};
+use crate::time::Monotonic;
+
+// Default supervision limits
+const DEFAULT_MAX_HOLD_MS: u32 = 30_000;
+const DEFAULT_MAX_TOGGLES: u8 = 20;
+const DEFAULT_TOGGLE_WINDOW_MS: u32 = 1000;
+const DEFAULT_RECOVERY_MS: u32 = 500;
+
+/// ## Description
+///
+/// Limits beyond which a supervised switch is considered faulty, in milliseconds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SupervisorConfig {
+    /// Maximum duration a switch can be held before being considered stuck
+    pub max_hold_ms: u32,
+    /// Maximum number of level changes within `toggle_window_ms` before the switch is considered chattering
+    pub max_toggles: u8,
+    /// Window in which the level changes are counted
+    pub toggle_window_ms: u32,
+    /// Duration the switch has to stay released before a fault is cleared
+    pub recovery_ms: u32,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        SupervisorConfig {
+            max_hold_ms: DEFAULT_MAX_HOLD_MS,
+            max_toggles: DEFAULT_MAX_TOGGLES,
+            toggle_window_ms: DEFAULT_TOGGLE_WINDOW_MS,
+            recovery_ms: DEFAULT_RECOVERY_MS,
+        }
+    }
+}
+
+/// ## Description
+///
+/// Faults detected by the supervision (independent from the error type of the supervised switch).
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Fault {
+    Stuck,
+    Chattering,
+}
+
+impl Fault {
+    fn to_error<E>(self) -> SwitchError<E> {
+        match self {
+            Fault::Stuck => SwitchError::Stuck,
+            Fault::Chattering => SwitchError::Chattering,
+        }
+    }
+}
+
+/// ## Description
+///
+/// Supervisory wrapper over any switch implementing the `Pressable` trait.
+/// Implements the Pressable trait.
+///
+/// On top of pin reading errors, the switch is flagged as faulty when:
+/// - it is held continuously for longer than `max_hold_ms` (`SwitchError::Stuck`)
+/// - its level changes more than `max_toggles` times within `toggle_window_ms` (`SwitchError::Chattering`)
+///
+/// While faulty, no event is reported: `get_current_state` returns `Faulty` and the other methods
+/// return the fault. The fault is cleared once the switch has stayed released for `recovery_ms`.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct SupervisedSwitch<SW, C>
+where
+    SW: Pressable,
+    C: Monotonic,
+{
+    switch: SW,
+    clock: C,
+    config: SupervisorConfig,
+    fault: Option<Fault>,
+    pressed: bool,
+    level_since: u64,
+    toggles: u8,
+    window_start: u64,
+    pending_press: bool,
+    pending_release: bool,
+    last_press_duration: Option<u64>,
+}
+
+impl<SW, C> SupervisedSwitch<SW, C>
+where
+    SW: Pressable,
+    C: Monotonic,
+{
+    /// ## Description
+    ///
+    /// Supervise a switch.
+    ///
+    /// ## Parameters
+    /// - `switch`: a switch implementing `hl_driver::switch::Pressable`
+    /// - `clock`: a clock implementing `hl_driver::time::Monotonic`
+    /// - `config`: the limits beyond which the switch is faulty
+    ///
+    /// ## Return
+    /// - SupervisedSwitch
+    pub fn new(switch: SW, clock: C, config: SupervisorConfig) -> Self {
+        let now = clock.now_ms();
+        SupervisedSwitch {
+            switch,
+            clock,
+            config,
+            fault: None,
+            pressed: false,
+            level_since: now,
+            toggles: 0,
+            window_start: now,
+            pending_press: false,
+            pending_release: false,
+            last_press_duration: None,
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Current fault of the switch.
+    ///
+    /// ## Return
+    /// - `Option<SwitchError>`: `None` if the switch behaves correctly, `Stuck` or `Chattering` otherwise
+    #[inline]
+    pub fn fault(&self) -> Option<SwitchError<SW::PinError>> {
+        self.fault.map(Fault::to_error)
+    }
+
+    /// ## Description
+    ///
+    /// Give back the supervised switch.
+    pub fn into_inner(self) -> SW {
+        self.switch
+    }
+
+    /// ## Description
+    ///
+    /// Sample the switch and update the supervision.
+    ///
+    /// ## Return
+    /// *Result<(), SwitchError>*
+    /// - `SwitchError`: the switch is faulty, or the error reported by the supervised switch
+    fn supervise(&mut self) -> Result<(), SwitchError<SW::PinError>> {
+        let pressed = match self.switch.try_get_current_state()? {
+            SwitchState::Pressed => true,
+            SwitchState::Released => false,
+            SwitchState::Transition | SwitchState::Faulty => self.pressed,
+        };
+        let now = self.clock.now_ms();
+
+        if pressed != self.pressed {
+            self.level_changed(pressed, now);
+        }
+
+        // Count the level changes in consecutive windows
+        if now - self.window_start >= self.config.toggle_window_ms as u64 {
+            self.window_start = now;
+            self.toggles = 0;
+        }
+
+        if self.toggles > self.config.max_toggles {
+            self.fault = Some(Fault::Chattering);
+        } else if self.pressed && now - self.level_since > self.config.max_hold_ms as u64 {
+            self.fault = Some(Fault::Stuck);
+        } else if self.fault.is_some()
+            && !self.pressed
+            && now - self.level_since >= self.config.recovery_ms as u64
+        {
+            self.fault = None;
+        }
+
+        match self.fault {
+            Some(fault) => {
+                // Events happening while faulty are dropped
+                self.pending_press = false;
+                self.pending_release = false;
+                Err(fault.to_error())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Record a change of level of the switch.
+    fn level_changed(&mut self, pressed: bool, now: u64) {
+        if pressed {
+            self.pending_press = true;
+        } else {
+            self.pending_release = true;
+            self.last_press_duration = Some(now - self.level_since);
+        }
+        self.pressed = pressed;
+        self.level_since = now;
+        self.toggles = self.toggles.saturating_add(1);
+    }
+}
+
+impl<SW, C> Pressable for SupervisedSwitch<SW, C>
+where
+    SW: Pressable,
+    C: Monotonic,
+{
+    type PinError = SW::PinError;
+
+    /// ## Description
+    ///
+    /// Return the state of the supervised switch.
+    ///
+    /// ## Return
+    /// *Result<SwitchState, SwitchError>*
+    /// - `SwitchState`: Pressed or Released
+    /// - `SwitchError`: the switch is faulty (`ReadPinState`, `Stuck` or `Chattering`)
+    #[inline]
+    fn try_get_current_state(&mut self) -> Result<SwitchState, SwitchError<Self::PinError>> {
+        self.supervise()?;
+        if self.pressed {
+            Ok(SwitchState::Pressed)
+        } else {
+            Ok(SwitchState::Released)
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Return if the switch has been pressed since the last use of this method.
+    ///
+    /// ## Return
+    /// *Result<bool, SwitchError>*
+    /// - `bool`: `true` if the switch has been pressed, `false` otherwise
+    /// - `SwitchError`: the switch is faulty (`ReadPinState`, `Stuck` or `Chattering`)
+    #[inline]
+    fn has_been_pressed(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        self.supervise()?;
+        Ok(core::mem::take(&mut self.pending_press))
+    }
+
+    /// ## Description
+    ///
+    /// Return if the switch has been released since the last use of this method.
+    ///
+    /// ## Return
+    /// *Result<bool, SwitchError>*
+    /// - `bool`: `true` if the switch has been released, `false` otherwise
+    /// - `SwitchError`: the switch is faulty (`ReadPinState`, `Stuck` or `Chattering`)
+    #[inline]
+    fn has_been_released(&mut self) -> Result<bool, SwitchError<Self::PinError>> {
+        self.supervise()?;
+        Ok(core::mem::take(&mut self.pending_release))
+    }
+
+    /// ## Description
+    ///
+    /// Return how long the switch was held during its last complete press.
+    ///
+    /// ## Return
+    /// - `Option<u64>`: duration in milliseconds, `None` before the first release
+    #[inline]
+    fn last_press_duration(&self) -> Option<u64> {
+        self.last_press_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockedClock, pull_up_switch};
+    use embedded_hal::digital::ErrorKind;
+
+    #[inline(never)]
+    #[test]
+    fn test_supervised_switch_stuck() {
+        let clock = MockedClock::default();
+        let mut switch =
+            SupervisedSwitch::new(pull_up_switch(), &clock, SupervisorConfig::default());
+
+        // A regular press is reported
+        switch.switch.pin.set_pressed(true);
+        assert_eq!(Ok(true), switch.has_been_pressed());
+
+        // Held for too long
+        clock.advance(30_001);
+        assert_eq!(Err(SwitchError::Stuck), switch.has_been_pressed());
+        assert_eq!(SwitchState::Faulty, switch.get_current_state());
+
+        // The release is suppressed and the fault kept until the recovery duration elapsed
+        switch.switch.pin.set_pressed(false);
+        assert_eq!(Err(SwitchError::Stuck), switch.has_been_released());
+        clock.advance(499);
+        assert_eq!(Err(SwitchError::Stuck), switch.has_been_released());
+        clock.advance(1);
+        assert_eq!(Ok(false), switch.has_been_released());
+        assert_eq!(None, switch.fault());
+
+        // Events are reported again
+        switch.switch.pin.set_pressed(true);
+        assert_eq!(Ok(true), switch.has_been_pressed());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_supervised_switch_chattering() {
+        let clock = MockedClock::default();
+        let mut switch =
+            SupervisedSwitch::new(pull_up_switch(), &clock, SupervisorConfig::default());
+
+        // 21 level changes in less than a second
+        for toggle in 0..21 {
+            switch.switch.pin.set_pressed(toggle % 2 == 0);
+            clock.advance(10);
+            let result = switch.has_been_pressed();
+            if toggle < 20 {
+                assert!(result.is_ok());
+            } else {
+                assert_eq!(Err(SwitchError::Chattering), result);
+            }
+        }
+
+        // Recovers once released and quiet
+        switch.switch.pin.set_pressed(false);
+        assert_eq!(Err(SwitchError::Chattering), switch.has_been_pressed());
+        clock.advance(1000);
+        assert_eq!(Ok(false), switch.has_been_pressed());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_supervised_switch_read_error() {
+        let clock = MockedClock::default();
+        let mut switch =
+            SupervisedSwitch::new(pull_up_switch(), &clock, SupervisorConfig::default());
+
+        switch.switch.pin.fault = true;
+        assert_eq!(
+            Err(SwitchError::ReadPinState(ErrorKind::Other)),
+            switch.has_been_pressed()
+        );
+        switch.switch.pin.fault = false;
+        assert_eq!(Ok(false), switch.has_been_pressed());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_supervised_switch_press_duration() {
+        let clock = MockedClock::default();
+        let mut switch =
+            SupervisedSwitch::new(pull_up_switch(), &clock, SupervisorConfig::default());
+
+        switch.switch.pin.set_pressed(true);
+        assert_eq!(Ok(true), switch.has_been_pressed());
+        clock.advance(2000);
+        switch.switch.pin.set_pressed(false);
+        assert_eq!(Ok(true), switch.has_been_released());
+        assert_eq!(Some(2000), switch.last_press_duration());
+    }
+}
@@ -0,0 +1,205 @@
+use crate::switch::{Pressable, SwitchError};
+
+/// ## Description
+///
+/// Change of state of a `ToggleSwitch`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ToggleEvent {
+    On,  // The toggle has been switched on
+    Off, // The toggle has been switched off
+}
+
+impl From<bool> for ToggleEvent {
+    fn from(value: bool) -> Self {
+        match value {
+            true => ToggleEvent::On,
+            false => ToggleEvent::Off,
+        }
+    }
+}
+
+/// ## Description
+///
+/// On/off control over any switch implementing the `Pressable` trait (mute, do-not-disturb, ...).
+/// Each press of the switch flips a persistent state.
+///
+/// The presses are the edges reported by `has_been_pressed`, so the toggle flips once per
+/// debounced press when the switch is debounced.
+///
+/// ## Example
+///
+/// See unit tests for example of use.
+#[derive(Debug)]
+pub struct ToggleSwitch<SW>
+where
+    SW: Pressable,
+{
+    switch: SW,
+    on: bool,
+}
+
+impl<SW> ToggleSwitch<SW>
+where
+    SW: Pressable,
+{
+    /// ## Description
+    ///
+    /// Create a toggle over a switch.
+    ///
+    /// ## Parameters
+    /// - `switch`: a switch implementing `hl_driver::switch::Pressable`
+    /// - `initial`: initial state of the toggle (`true` for on)
+    ///
+    /// ## Return
+    /// - ToggleSwitch
+    pub fn new(switch: SW, initial: bool) -> Self {
+        ToggleSwitch {
+            switch,
+            on: initial,
+        }
+    }
+
+    /// ## Description
+    ///
+    /// Current state of the toggle.
+    ///
+    /// ## Return
+    /// - `bool`: `true` if the toggle is on
+    #[inline]
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// ## Description
+    ///
+    /// Set the state of the toggle from outside (e.g. restored settings, remote control).
+    ///
+    /// ## Parameters
+    /// - `on`: new state of the toggle
+    ///
+    /// ## Return
+    /// - `Option<ToggleEvent>`: the change of state, `None` if the toggle already was in this state
+    pub fn set(&mut self, on: bool) -> Option<ToggleEvent> {
+        if self.on == on {
+            return None;
+        }
+        self.on = on;
+        Some(ToggleEvent::from(on))
+    }
+
+    /// ## Description
+    ///
+    /// Give back the switch used by the toggle.
+    ///
+    /// ## Return
+    /// - `SW`: the wrapped switch
+    pub fn into_inner(self) -> SW {
+        self.switch
+    }
+
+    /// ## Description
+    ///
+    /// Sample the switch and flip the toggle if it has been pressed.
+    /// Has to be called periodically.
+    ///
+    /// ## Return
+    /// *Result<Option<ToggleEvent>, SwitchError>*
+    /// - `Option<ToggleEvent>`: the change of state which happened during this poll, if any
+    /// - `SwitchError`: the error reported by the switch (e.g. `ReadPinState`)
+    pub fn poll(&mut self) -> Result<Option<ToggleEvent>, SwitchError<SW::PinError>> {
+        if self.switch.has_been_pressed()? {
+            Ok(self.set(!self.on))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debounce::LatchingDebouncer;
+    use crate::switch::Switch;
+    use crate::test_utils::MockedGpioPin;
+    use embedded_hal::digital::{ErrorKind, PinState};
+
+    #[inline(never)]
+    #[test]
+    fn test_toggle_on_press() {
+        // Pull Up switch with Low level when pressed, initially released
+        let pin = MockedGpioPin {
+            state: PinState::High,
+            fault: false,
+        };
+        let mut toggle = ToggleSwitch::new(Switch::new(pin, PinState::Low), false);
+
+        assert_eq!(Ok(None), toggle.poll());
+        assert!(!toggle.is_on());
+
+        // Press: on, holding does not flip again
+        toggle.switch.pin.state = PinState::Low;
+        assert_eq!(Ok(Some(ToggleEvent::On)), toggle.poll());
+        assert_eq!(Ok(None), toggle.poll());
+        assert!(toggle.is_on());
+
+        // Release then press again: off
+        toggle.switch.pin.state = PinState::High;
+        assert_eq!(Ok(None), toggle.poll());
+        toggle.switch.pin.state = PinState::Low;
+        assert_eq!(Ok(Some(ToggleEvent::Off)), toggle.poll());
+        assert!(!toggle.is_on());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_toggle_debounced() {
+        let pin = MockedGpioPin {
+            state: PinState::High,
+            fault: false,
+        };
+        let switch = Switch::new(pin, PinState::Low).with_debounce(LatchingDebouncer::default());
+        let mut toggle = ToggleSwitch::new(switch, true);
+
+        // A bouncing press held for a while only flips the toggle once
+        let mut events = 0;
+        let bounces = [PinState::Low, PinState::High, PinState::Low, PinState::High];
+        for state in bounces.into_iter().chain([PinState::Low; 10]) {
+            toggle.switch.switch.pin.state = state;
+            if toggle
+                .poll()
+                .expect("Problem when reading the pin")
+                .is_some()
+            {
+                events += 1;
+            }
+        }
+        assert_eq!(1, events);
+        assert!(!toggle.is_on());
+    }
+
+    #[inline(never)]
+    #[test]
+    fn test_toggle_set_externally() {
+        let pin = MockedGpioPin {
+            state: PinState::High,
+            fault: false,
+        };
+        let mut toggle = ToggleSwitch::new(Switch::new(pin, PinState::Low), false);
+
+        assert_eq!(Some(ToggleEvent::On), toggle.set(true));
+        assert_eq!(None, toggle.set(true));
+        assert!(toggle.is_on());
+
+        // The next press flips the externally set state
+        toggle.switch.pin.state = PinState::Low;
+        assert_eq!(Ok(Some(ToggleEvent::Off)), toggle.poll());
+
+        // Reading errors are reported and keep the state
+        toggle.switch.pin.fault = true;
+        assert_eq!(
+            Err(SwitchError::ReadPinState(ErrorKind::Other)),
+            toggle.poll()
+        );
+        assert!(!toggle.is_on());
+    }
+}
@@ -1,4 +1,16 @@
-use embedded_hal::digital::{ErrorKind, ErrorType, InputPin, PinState};
+use core::cell::Cell;
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use embedded_hal::digital::{ErrorKind, ErrorType, InputPin, OutputPin, PinState};
+use embedded_hal::i2c::{self, I2c, NoAcknowledgeSource, Operation};
+use embedded_hal_async::{delay::DelayNs, digital::Wait};
+
+use crate::adc::AnalogInput;
+use crate::encoder::{Direction, Encode, EncoderError};
+use crate::switch::Switch;
+use crate::time::Monotonic;
 
 /// ## Description
 /// Mock of a simple gpio pin for unit tests
@@ -35,3 +47,579 @@ impl InputPin for MockedGpioPin {
         }
     }
 }
+
+/// ## Description
+/// Waiting on the mocked pin simulates the awaited edge: the pin immediately takes the awaited level.
+impl Wait for MockedGpioPin {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(PinState::High)
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(PinState::Low)
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(PinState::High)
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(PinState::Low)
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(!self.state)
+    }
+}
+
+impl MockedGpioPin {
+    /// Press or release a pull-up switch (pressed at `PinState::Low`) wired to the pin.
+    pub fn set_pressed(&mut self, pressed: bool) {
+        self.state = if pressed {
+            PinState::Low
+        } else {
+            PinState::High
+        };
+    }
+
+    fn wait_for(&mut self, state: PinState) -> Result<(), ErrorKind> {
+        match self.fault {
+            true => Err(ErrorKind::Other),
+            false => {
+                self.state = state;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// ## Description
+/// Pull-up switch (pressed at `PinState::Low`) on a mocked pin, initially released.
+pub fn pull_up_switch() -> Switch<MockedGpioPin> {
+    let pin = MockedGpioPin {
+        state: PinState::High,
+        fault: false,
+    };
+    Switch::new(pin, PinState::Low)
+}
+
+/// ## Description
+/// Mocked gpio pin counting the waits, to check when an async driver sleeps.
+/// Like `MockedGpioPin`, each wait immediately completes on the awaited level.
+#[derive(Default)]
+pub struct MockedWaitPin {
+    pub pin: MockedGpioPin,
+    pub waits: u32,
+}
+
+impl ErrorType for MockedWaitPin {
+    type Error = ErrorKind;
+}
+
+impl InputPin for MockedWaitPin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.pin.is_high()
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.pin.is_low()
+    }
+}
+
+impl Wait for MockedWaitPin {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.waits += 1;
+        self.pin.wait_for_high().await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.waits += 1;
+        self.pin.wait_for_low().await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.waits += 1;
+        self.pin.wait_for_rising_edge().await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.waits += 1;
+        self.pin.wait_for_falling_edge().await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.waits += 1;
+        self.pin.wait_for_any_edge().await
+    }
+}
+
+/// ## Description
+/// Mock of an async delay which returns immediately and records the requested durations.
+#[derive(Default)]
+pub struct MockedDelay {
+    pub elapsed_ns: u64,
+}
+
+impl DelayNs for MockedDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += ns as u64;
+    }
+}
+
+/// ## Description
+/// Minimal executor running a future to completion, for unit tests of async drivers.
+/// The futures under test are expected to never stay pending (mocks complete immediately).
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// ## Description
+/// Mock of a monotonic clock for unit tests.
+/// The time only moves when the test advances it.
+#[derive(Default)]
+pub struct MockedClock {
+    now: Cell<u64>,
+}
+
+impl MockedClock {
+    /// ## Description
+    /// Move the clock forward by the given number of milliseconds.
+    pub fn advance(&self, ms: u64) {
+        self.now.set(self.now.get() + ms);
+    }
+}
+
+impl Monotonic for MockedClock {
+    fn now_ms(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+/// ## Description
+/// Mock of a keypad matrix without diodes, for unit tests.
+/// Row and column pins are borrowed from the matrix, pressing several keys reproduces ghosting.
+pub struct MockedMatrix {
+    cols: usize,
+    keys: Cell<u64>,
+    driven_rows: Cell<u64>,
+    column_fault: Cell<bool>,
+}
+
+impl MockedMatrix {
+    pub fn new(cols: usize) -> Self {
+        MockedMatrix {
+            cols,
+            keys: Cell::new(0),
+            driven_rows: Cell::new(0),
+            column_fault: Cell::new(false),
+        }
+    }
+
+    /// Make the column pins fail on read.
+    pub fn set_column_fault(&self, fault: bool) {
+        self.column_fault.set(fault);
+    }
+
+    /// Rows currently driven low, bit `n` for row `n`.
+    pub fn driven_rows(&self) -> u64 {
+        self.driven_rows.get()
+    }
+
+    pub fn press(&self, row: usize, col: usize) {
+        self.keys
+            .set(self.keys.get() | 1 << (row * self.cols + col));
+    }
+
+    pub fn release(&self, row: usize, col: usize) {
+        self.keys
+            .set(self.keys.get() & !(1 << (row * self.cols + col)));
+    }
+
+    pub fn row(&self, row: usize) -> MockedRowPin<'_> {
+        MockedRowPin { matrix: self, row }
+    }
+
+    pub fn col(&self, col: usize) -> MockedColumnPin<'_> {
+        MockedColumnPin { matrix: self, col }
+    }
+
+    /// Columns pulled low by the driven rows, propagated through the pressed keys.
+    fn low_columns(&self) -> u64 {
+        let keys = self.keys.get();
+        let rows_count = 64 / self.cols;
+        let mut rows = self.driven_rows.get();
+        let mut cols = 0;
+        loop {
+            // Columns connected to the low rows
+            let new_cols = (0..rows_count)
+                .filter(|row| rows & 1 << row != 0)
+                .fold(0, |acc, row| {
+                    acc | (keys >> (row * self.cols)) & ((1 << self.cols) - 1)
+                });
+            // Rows connected to the low columns
+            let new_rows = (0..rows_count)
+                .filter(|row| (keys >> (row * self.cols)) & new_cols != 0)
+                .fold(rows, |acc, row| acc | 1 << row);
+            if new_cols == cols && new_rows == rows {
+                return cols;
+            }
+            cols = new_cols;
+            rows = new_rows;
+        }
+    }
+}
+
+/// ## Description
+/// Row pin of a `MockedMatrix`, the row is driven when the pin is low.
+pub struct MockedRowPin<'a> {
+    matrix: &'a MockedMatrix,
+    row: usize,
+}
+
+impl ErrorType for MockedRowPin<'_> {
+    type Error = ErrorKind;
+}
+
+impl OutputPin for MockedRowPin<'_> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let driven = self.matrix.driven_rows.get();
+        self.matrix.driven_rows.set(driven | 1 << self.row);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let driven = self.matrix.driven_rows.get();
+        self.matrix.driven_rows.set(driven & !(1 << self.row));
+        Ok(())
+    }
+}
+
+/// ## Description
+/// Column pin of a `MockedMatrix`, pulled up unless connected to a driven row.
+pub struct MockedColumnPin<'a> {
+    matrix: &'a MockedMatrix,
+    col: usize,
+}
+
+impl ErrorType for MockedColumnPin<'_> {
+    type Error = ErrorKind;
+}
+
+impl InputPin for MockedColumnPin<'_> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        if self.matrix.column_fault.get() {
+            return Err(ErrorKind::Other);
+        }
+        Ok(self.matrix.low_columns() & 1 << self.col == 0)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        if self.matrix.column_fault.get() {
+            return Err(ErrorKind::Other);
+        }
+        Ok(self.matrix.low_columns() & 1 << self.col != 0)
+    }
+}
+
+/// ## Description
+/// Mock of a PCF8574 port expander on an I2C bus, for unit tests.
+/// The port is quasi-bidirectional: a pin reads low when its latch is low or when it is pulled low
+/// externally (`levels`). The interrupt is asserted when an input changes and cleared by a read.
+pub struct MockedPcf8574 {
+    pub address: u8,
+    pub latch: u8,
+    pub levels: u8,
+    pub interrupt: bool,
+    pub fault: bool,
+}
+
+impl MockedPcf8574 {
+    pub fn new(address: u8) -> Self {
+        MockedPcf8574 {
+            address,
+            latch: 0xFF,
+            levels: 0xFF,
+            interrupt: false,
+            fault: false,
+        }
+    }
+
+    /// Change the external levels of the pins, asserting the interrupt if an input changed.
+    pub fn set_levels(&mut self, levels: u8) {
+        if (levels ^ self.levels) & self.latch != 0 {
+            self.interrupt = true;
+        }
+        self.levels = levels;
+    }
+}
+
+impl i2c::ErrorType for MockedPcf8574 {
+    type Error = i2c::ErrorKind;
+}
+
+impl I2c for MockedPcf8574 {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.fault {
+            return Err(i2c::ErrorKind::Bus);
+        }
+        if address != self.address {
+            return Err(i2c::ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    if let Some(byte) = bytes.last() {
+                        self.latch = *byte;
+                    }
+                }
+                Operation::Read(buffer) => {
+                    buffer.fill(self.latch & self.levels);
+                    self.interrupt = false;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ## Description
+/// Mock of a MCP23017 port expander on an I2C bus (`IOCON.BANK = 0`), for unit tests.
+/// Registers are accessed sequentially from the register pointer, `GPIO` reads the external `levels`
+/// of the inputs and the latch of the outputs. The interrupt is asserted when an input with
+/// interrupt-on-change enabled changes and cleared by reading `GPIO`.
+pub struct MockedMcp23017 {
+    pub address: u8,
+    pub registers: [u8; 0x16],
+    pub levels: u16,
+    pub interrupt: bool,
+    pub fault: bool,
+    pointer: usize,
+}
+
+impl MockedMcp23017 {
+    const IODIR: usize = 0x00;
+    const GPINTEN: usize = 0x04;
+    const GPIO: usize = 0x12;
+    const OLAT: usize = 0x14;
+
+    pub fn new(address: u8) -> Self {
+        let mut registers = [0; 0x16];
+        // All pins are inputs at power on
+        registers[Self::IODIR] = 0xFF;
+        registers[Self::IODIR + 1] = 0xFF;
+        MockedMcp23017 {
+            address,
+            registers,
+            levels: 0xFFFF,
+            interrupt: false,
+            fault: false,
+            pointer: 0,
+        }
+    }
+
+    /// Value of a pair of A/B registers.
+    pub fn register(&self, register: usize) -> u16 {
+        u16::from_le_bytes([self.registers[register], self.registers[register + 1]])
+    }
+
+    /// Change the external levels of the pins, asserting the interrupt if a monitored input changed.
+    pub fn set_levels(&mut self, levels: u16) {
+        let monitored = self.register(Self::IODIR) & self.register(Self::GPINTEN);
+        if (levels ^ self.levels) & monitored != 0 {
+            self.interrupt = true;
+        }
+        self.levels = levels;
+    }
+
+    fn read_register(&mut self, register: usize) -> u8 {
+        match register {
+            Self::GPIO | 0x13 => {
+                let inputs = self.register(Self::IODIR);
+                let port = self.levels & inputs | self.register(Self::OLAT) & !inputs;
+                self.interrupt = false;
+                port.to_le_bytes()[register - Self::GPIO]
+            }
+            _ => self.registers[register],
+        }
+    }
+}
+
+impl i2c::ErrorType for MockedMcp23017 {
+    type Error = i2c::ErrorKind;
+}
+
+impl I2c for MockedMcp23017 {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.fault {
+            return Err(i2c::ErrorKind::Bus);
+        }
+        if address != self.address {
+            return Err(i2c::ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    let Some((register, data)) = bytes.split_first() else {
+                        continue;
+                    };
+                    self.pointer = *register as usize;
+                    for byte in data {
+                        // Writing GPIO writes the output latch
+                        let register = match self.pointer {
+                            Self::GPIO | 0x13 => self.pointer + 2,
+                            register => register,
+                        };
+                        self.registers[register] = *byte;
+                        self.pointer = (self.pointer + 1) % self.registers.len();
+                    }
+                }
+                Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = self.read_register(self.pointer);
+                        self.pointer = (self.pointer + 1) % self.registers.len();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ## Description
+/// Mock of an AS5600 magnetic angle sensor on an I2C bus, for unit tests.
+/// Registers are read sequentially from the register pointer, the `STATUS`, `RAW ANGLE` and `ANGLE`
+/// registers report the `status` and `angle` set by the test.
+pub struct MockedAs5600 {
+    pub angle: u16,
+    pub status: u8,
+    pub fault: bool,
+    pointer: u8,
+}
+
+impl MockedAs5600 {
+    const ADDRESS: u8 = 0x36;
+    // Magnet detected
+    const STATUS_MD: u8 = 0x20;
+
+    pub fn new(angle: u16) -> Self {
+        MockedAs5600 {
+            angle,
+            status: Self::STATUS_MD,
+            fault: false,
+            pointer: 0,
+        }
+    }
+
+    fn read_register(&self, register: u8) -> u8 {
+        let [high, low] = self.angle.to_be_bytes();
+        match register {
+            0x0B => self.status,
+            0x0C | 0x0E => high,
+            0x0D | 0x0F => low,
+            _ => 0,
+        }
+    }
+}
+
+impl i2c::ErrorType for MockedAs5600 {
+    type Error = i2c::ErrorKind;
+}
+
+impl I2c for MockedAs5600 {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.fault {
+            return Err(i2c::ErrorKind::Bus);
+        }
+        if address != Self::ADDRESS {
+            return Err(i2c::ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    if let Some(register) = bytes.first() {
+                        self.pointer = *register;
+                    }
+                }
+                Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = self.read_register(self.pointer);
+                        self.pointer = self.pointer.wrapping_add(1);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ## Description
+/// Mock of an analog input for unit tests, returning the voltage set by the test.
+pub struct MockedAdc {
+    pub millivolts: u16,
+    pub fault: bool,
+}
+
+impl MockedAdc {
+    pub fn new(millivolts: u16) -> Self {
+        MockedAdc {
+            millivolts,
+            fault: false,
+        }
+    }
+}
+
+impl AnalogInput for MockedAdc {
+    type Error = ();
+
+    fn read_mv(&mut self) -> Result<u16, Self::Error> {
+        match self.fault {
+            true => Err(()),
+            false => Ok(self.millivolts),
+        }
+    }
+}
+
+/// ## Description
+/// Mock of an encoder playing a scripted rotation for unit tests, one direction per `encode` call.
+/// The encoder is at rest once the script is over.
+pub struct MockedEncoder<'a> {
+    script: &'a [Direction],
+}
+
+impl<'a> MockedEncoder<'a> {
+    pub fn new(script: &'a [Direction]) -> Self {
+        MockedEncoder { script }
+    }
+}
+
+impl Encode for MockedEncoder<'_> {
+    type PinError = ErrorKind;
+
+    fn try_encode(&mut self) -> Result<Direction, EncoderError<Self::PinError>> {
+        match self.script.split_first() {
+            Some((direction, rest)) => {
+                self.script = rest;
+                Ok(*direction)
+            }
+            None => Ok(Direction::Rest),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+/// ## Description
+///
+/// Trait defining a monotonic clock with a millisecond resolution.
+/// Used by drivers which need to measure durations independently of their polling rate.
+pub trait Monotonic {
+    /// ## Description
+    /// Time elapsed since an arbitrary origin (usually the boot of the device).
+    /// The returned value must never decrease.
+    /// ## Return
+    /// - `u64`: milliseconds elapsed since the origin
+    fn now_ms(&self) -> u64;
+
+    /// ## Description
+    /// Indicate if the clock measures time. Drivers do not report durations from a stopped clock.
+    /// ## Return
+    /// - `bool`: `true` unless the clock is a placeholder such as `NoClock`
+    #[inline]
+    fn is_running(&self) -> bool {
+        true
+    }
+}
+
+/// ## Description
+///
+/// Allow a clock to be shared by reference between several drivers.
+impl<C> Monotonic for &C
+where
+    C: Monotonic + ?Sized,
+{
+    #[inline]
+    fn now_ms(&self) -> u64 {
+        (**self).now_ms()
+    }
+
+    #[inline]
+    fn is_running(&self) -> bool {
+        (**self).is_running()
+    }
+}
+
+/// ## Description
+///
+/// Placeholder for drivers used without clock.
+/// Always returns 0 and is never running, so drivers relying on it do not measure any duration.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct NoClock;
+
+impl Monotonic for NoClock {
+    #[inline]
+    fn now_ms(&self) -> u64 {
+        0
+    }
+
+    #[inline]
+    fn is_running(&self) -> bool {
+        false
+    }
+}